@@ -0,0 +1,23 @@
+use crate::parser::VarType;
+use crate::nodes::Node;
+use super::*;
+
+// the length is a part of the array type, so it is known without evaluating the array
+fn lower_len(args: &Vec<Box<Node>>) -> String{
+    let array: String = args[0].to_c();
+    return format!("(sizeof(({}).data) / sizeof(({}).data[0]))", array, array);
+}
+
+// the strings have a len() of their own, in the string module
+fn len(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    let lowering = match arg_types.as_slice(){
+        [VarType::Array(_element, _length)] => Lowering::Custom(lower_len),
+        [list @ VarType::List(_element)]    => call(&format!("{}_len", list.to_c().trim())),
+        _ => return None,
+    };
+    return Some(Builtin {arg_types: arg_types.clone(), return_type: VarType::U64, lowering: lowering, library: None});
+}
+
+pub fn register(registry: &mut Registry){
+    registry.add_generic("len", len);
+}
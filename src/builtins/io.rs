@@ -0,0 +1,51 @@
+use crate::parser::VarType;
+use crate::nodes::Node;
+use crate::runtime;
+use super::*;
+use super::string::{parsed, parse_result};
+
+// interpolated strings are printed directly, without formatting them into a new string first
+fn lower_print(args: &Vec<Box<Node>>) -> String{
+    let mut result: String = "printf(".to_owned();
+    match &*args[0]{
+        Node::InterpolatedString(val) => result.push_str(&val.to_c_printf_args()),
+        val => {
+            result.push_str("\"%s\", ");
+            result.push_str(&val.to_c());
+        },
+    }
+    result.push(')');
+    return result;
+}
+
+// reads a line and parses it, the end of the input is an error too
+fn read_result(value_type: VarType) -> String{
+    let parse: String = parse_result(value_type.clone());
+    let helper: String = format!("__chal_read_{}", value_type);
+    let result_type: String = parsed(value_type).to_c().trim().to_string();
+    runtime::declare_function(
+        &helper,
+        format!("{} {}(void)", result_type, helper),
+        format!(
+            "{{\nstr line = __chal_input();\nif(line[0] == '\\0' && feof(stdin)){{\nfree(line);\nreturn (({}){{.is_ok = 0, .error = \"the input ended\"}});\n}}\n{} result = {}(line);\nfree(line);\nreturn result;\n}}\n",
+            result_type, result_type, parse
+        ),
+    );
+    return helper;
+}
+
+fn lower_read_i64(_args: &Vec<Box<Node>>) -> String{
+    return format!("{}()", read_result(VarType::I64));
+}
+
+fn lower_read_f64(_args: &Vec<Box<Node>>) -> String{
+    return format!("{}()", read_result(VarType::F64));
+}
+
+pub fn register(registry: &mut Registry){
+    registry.add("print",     vec![VarType::Str], VarType::None, Lowering::Custom(lower_print));
+    registry.add("input",     vec![], VarType::Str, call("__chal_input"));
+    registry.add("read_line", vec![], VarType::Str, call("__chal_input"));
+    registry.add("read_i64",  vec![], parsed(VarType::I64), Lowering::Custom(lower_read_i64));
+    registry.add("read_f64",  vec![], parsed(VarType::F64), Lowering::Custom(lower_read_f64));
+}
@@ -0,0 +1,52 @@
+use crate::parser::VarType;
+use super::*;
+
+// the functions of every list type are generated in the runtime - "__list_i32_push" and so on
+fn list_function(list: &VarType, name: &str, arg_types: Vec<VarType>, return_type: VarType) -> Option<Builtin>{
+    let c_name: String = format!("{}{}", list.to_c().trim(), name);
+    return Some(Builtin {arg_types: arg_types, return_type: return_type, lowering: method(&c_name), library: None});
+}
+
+fn push(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [list @ VarType::List(element), _value] => list_function(list, "_push", vec![list.clone(), *element.clone()], VarType::None),
+        _ => None,
+    }
+}
+
+fn pop(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [list @ VarType::List(element)] => list_function(list, "_pop", vec![list.clone()], *element.clone()),
+        _ => None,
+    }
+}
+
+fn insert(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [list @ VarType::List(element), _index, _value] => list_function(list, "_insert", vec![list.clone(), VarType::I64, *element.clone()], VarType::None),
+        _ => None,
+    }
+}
+
+fn remove(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [list @ VarType::List(element), _index] => list_function(list, "_remove", vec![list.clone(), VarType::I64], *element.clone()),
+        _ => None,
+    }
+}
+
+// the lists own their elements' memory, but not the memory the elements point to
+fn free(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [list @ VarType::List(_element)] => list_function(list, "_free", vec![list.clone()], VarType::None),
+        _ => None,
+    }
+}
+
+pub fn register(registry: &mut Registry){
+    registry.add_generic("push",   push);
+    registry.add_generic("pop",    pop);
+    registry.add_generic("insert", insert);
+    registry.add_generic("remove", remove);
+    registry.add_generic("free",   free);
+}
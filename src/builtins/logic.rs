@@ -0,0 +1,7 @@
+use crate::parser::VarType;
+use super::*;
+
+pub fn register(registry: &mut Registry){
+    registry.add_constant("true",  VarType::Bool, "1");
+    registry.add_constant("false", VarType::Bool, "0");
+}
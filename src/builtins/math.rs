@@ -0,0 +1,36 @@
+use crate::parser::VarType;
+use super::*;
+
+const NUMERIC_TYPES: [VarType; 10] = [
+    VarType::I8, VarType::I16, VarType::I32, VarType::I64,
+    VarType::U8, VarType::U16, VarType::U32, VarType::U64,
+    VarType::F32, VarType::F64,
+];
+
+// the <math.h> functions come in a 'float' version suffixed with 'f' and a 'double' one
+const FLOAT_TYPES: [(VarType, &str); 2] = [(VarType::F32, "f"), (VarType::F64, "")];
+
+pub fn register(registry: &mut Registry){
+    // these are implemented in the runtime once for every numeric type
+    for var_type in NUMERIC_TYPES{
+        let helper = |name: &str| call(&format!("__chal_{}_{}", name, var_type));
+        let t = &var_type;
+        registry.add("abs",   vec![t.clone()], t.clone(), helper("abs"));
+        registry.add("min",   vec![t.clone(), t.clone()], t.clone(), helper("min"));
+        registry.add("max",   vec![t.clone(), t.clone()], t.clone(), helper("max"));
+        registry.add("clamp", vec![t.clone(), t.clone(), t.clone()], t.clone(), helper("clamp"));
+    }
+
+    for (var_type, suffix) in FLOAT_TYPES{
+        let function = |name: &str| call(&format!("{}{}", name, suffix));
+        for name in ["sqrt", "floor", "ceil", "round", "sin", "cos", "tan", "log", "exp"]{
+            registry.add_linked(name, vec![var_type.clone()], var_type.clone(), function(name), Some("m"));
+        }
+        for name in ["pow", "atan2"]{
+            registry.add_linked(name, vec![var_type.clone(), var_type.clone()], var_type.clone(), function(name), Some("m"));
+        }
+    }
+
+    registry.add_constant("PI", VarType::F64, "3.14159265358979323846");
+    registry.add_constant("E",  VarType::F64, "2.71828182845904523536");
+}
@@ -0,0 +1,19 @@
+use crate::parser::VarType;
+use super::*;
+
+// releases the memory of alloc()
+fn free(arg_types: &Vec<VarType>) -> Option<Builtin>{
+    match arg_types.as_slice(){
+        [pointer @ VarType::Pointer(target)] if **target != VarType::None => {
+            return Some(Builtin {arg_types: vec![pointer.clone()], return_type: VarType::None, lowering: call("free"), library: None});
+        },
+        _ => None,
+    }
+}
+
+pub fn register(registry: &mut Registry){
+    // releases the strings made by input() and the interpolated strings
+    registry.add("free", vec![VarType::Str], VarType::None, call("free"));
+    registry.add_generic("free", free);
+    registry.add_constant("null", VarType::Pointer(Box::new(VarType::None)), "NULL");
+}
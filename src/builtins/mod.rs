@@ -0,0 +1,143 @@
+pub mod io;
+pub mod string;
+pub mod math;
+pub mod mem;
+pub mod array;
+pub mod list;
+pub mod logic;
+
+use crate::parser::VarType;
+use crate::nodes::Node;
+use std::collections::HashMap;
+
+// how a call to a built-in function is turned into C
+#[derive(Debug, Clone)]
+pub enum Lowering{
+    // a plain call to the runtime (or libc) function with the given name
+    Call(String),
+    // a call to a runtime function changing its first argument, which is passed by its address
+    Method(String),
+    // anything else - generated from the arguments of the call
+    Custom(fn(&Vec<Box<Node>>) -> String),
+}
+
+impl Lowering{
+    pub fn to_c(&self, args: &Vec<Box<Node>>) -> String{
+        match self{
+            Lowering::Custom(lower) => return lower(args),
+            Lowering::Call(name) => {
+                let mut result: String = name.to_string();
+                result.push('(');
+                for i in 0..args.len(){
+                    result.push_str(&args[i].to_c());
+                    if i != args.len() - 1 {result.push_str(", ");}
+                }
+                result.push(')');
+                return result;
+            },
+            Lowering::Method(name) => {
+                let mut result: String = name.to_string();
+                result.push_str("(&");
+                for i in 0..args.len(){
+                    result.push_str(&args[i].to_c());
+                    if i != args.len() - 1 {result.push_str(", ");}
+                }
+                result.push(')');
+                return result;
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Builtin{
+    pub arg_types: Vec<VarType>,
+    pub return_type: VarType,
+    pub lowering: Lowering,
+    pub library: Option<&'static str>, // what has to be linked to the program when it is used
+}
+
+#[derive(Debug, Clone)]
+pub struct Constant{
+    pub var_type: VarType,
+    pub value: &'static str,
+}
+
+// builds the signature of a generic function for the given argument types, if it takes them
+pub type Generic = fn(&Vec<VarType>) -> Option<Builtin>;
+
+// a function name may have several overloads, which are resolved by the semantic pass
+pub struct Registry{
+    functions: HashMap<&'static str, Vec<Builtin>>,
+    generics: HashMap<&'static str, Vec<Generic>>, // tried in the order they were added
+    constants: HashMap<&'static str, Constant>,
+}
+
+impl Registry{
+    fn new() -> Self{
+        Registry {functions: HashMap::new(), generics: HashMap::new(), constants: HashMap::new()}
+    }
+
+    pub fn add_generic(&mut self, name: &'static str, generic: Generic){
+        self.generics.entry(name).or_default().push(generic);
+    }
+
+    pub fn add(&mut self, name: &'static str, arg_types: Vec<VarType>, return_type: VarType, lowering: Lowering){
+        self.add_linked(name, arg_types, return_type, lowering, None);
+    }
+
+    pub fn add_linked(&mut self, name: &'static str, arg_types: Vec<VarType>, return_type: VarType, lowering: Lowering, library: Option<&'static str>){
+        self.functions
+            .entry(name)
+            .or_default()
+            .push(Builtin {arg_types: arg_types, return_type: return_type, lowering: lowering, library: library});
+    }
+
+    pub fn add_constant(&mut self, name: &'static str, var_type: VarType, value: &'static str){
+        self.constants.insert(name, Constant {var_type: var_type, value: value});
+    }
+}
+
+pub fn call(name: &str) -> Lowering{
+    Lowering::Call(name.to_string())
+}
+
+pub fn method(name: &str) -> Lowering{
+    Lowering::Method(name.to_string())
+}
+
+// every module of the standard library adds its functions here
+lazy_static::lazy_static!{
+    static ref BUILTINS: Registry = {
+        let mut registry = Registry::new();
+        io::register(&mut registry);
+        string::register(&mut registry);
+        math::register(&mut registry);
+        mem::register(&mut registry);
+        array::register(&mut registry);
+        list::register(&mut registry);
+        logic::register(&mut registry);
+        registry
+    };
+}
+
+pub fn get(name: &str) -> Option<Vec<Builtin>>{
+    return BUILTINS.functions.get(name).cloned();
+}
+
+// the generic functions are tried before the overloads with the same name
+pub fn get_generic(name: &str, arg_types: &Vec<VarType>) -> Option<Builtin>{
+    return BUILTINS.generics.get(name)?.iter().find_map(|generic| generic(arg_types));
+}
+
+// the built-in functions taking the type of whatever they are assigned to - the constructors of
+// the options and the results, and alloc() - which the parser turns into nodes of their own
+pub const CONSTRUCTORS: [&str; 4] = ["some", "ok", "err", "alloc"];
+
+pub fn is_builtin(name: &str) -> bool{
+    return BUILTINS.functions.contains_key(name) || BUILTINS.generics.contains_key(name) || CONSTRUCTORS.contains(&name);
+}
+
+pub fn get_constant(name: &str) -> Option<Constant>{
+    return BUILTINS.constants.get(name).cloned();
+}
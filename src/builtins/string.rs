@@ -0,0 +1,53 @@
+use crate::parser::VarType;
+use crate::nodes::Node;
+use crate::runtime;
+use super::*;
+
+fn lower_equals(args: &Vec<Box<Node>>) -> String{
+    let mut result: String = "(strcmp(".to_owned();
+    result.push_str(&args[0].to_c());
+    result.push_str(", ");
+    result.push_str(&args[1].to_c());
+    result.push_str(") == 0)");
+    return result;
+}
+
+// the result of a parse is made by a helper declared with its result type, which the prelude comes
+// before - "__chal_parse_int_result("12x")" is an error, "'12x' is not a valid i64"
+// result[i64, str] - the value, or why the string isn't one
+pub fn parsed(value_type: VarType) -> VarType{
+    return VarType::Result(Box::new(value_type), Box::new(VarType::Str));
+}
+
+pub fn parse_result(value_type: VarType) -> String{
+    let (parse, helper) = match value_type{
+        VarType::I64 => ("__chal_parse_int", "__chal_parse_int_result"),
+        _ => ("__chal_parse_float", "__chal_parse_float_result"),
+    };
+    let result_type: String = VarType::Result(Box::new(value_type.clone()), Box::new(VarType::Str)).to_c().trim().to_string();
+    runtime::declare_function(
+        &helper.to_string(),
+        format!("{} {}(str s)", result_type, helper),
+        format!(
+            "{{\n{}value;\nif({}(s, &value)) return (({}){{.is_ok = 1, .value = value}});\nreturn (({}){{.is_ok = 0, .error = __chal_format(\"'%s' is not a valid {}\", s)}});\n}}\n",
+            value_type.to_c(), parse, result_type, result_type, value_type
+        ),
+    );
+    return helper.to_string();
+}
+
+fn lower_parse_int(args: &Vec<Box<Node>>) -> String{
+    return format!("{}({})", parse_result(VarType::I64), args[0].to_c());
+}
+
+fn lower_parse_float(args: &Vec<Box<Node>>) -> String{
+    return format!("{}({})", parse_result(VarType::F64), args[0].to_c());
+}
+
+pub fn register(registry: &mut Registry){
+    registry.add("len",          vec![VarType::Str], VarType::U64, call("strlen"));
+    registry.add("concat",       vec![VarType::Str, VarType::Str], VarType::Str, call("__chal_concat"));
+    registry.add("equals",       vec![VarType::Str, VarType::Str], VarType::Bool, Lowering::Custom(lower_equals));
+    registry.add("parse_int",    vec![VarType::Str], parsed(VarType::I64), Lowering::Custom(lower_parse_int));
+    registry.add("parse_float",  vec![VarType::Str], parsed(VarType::F64), Lowering::Custom(lower_parse_float));
+}
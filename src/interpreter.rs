@@ -1,16 +1,140 @@
 use crate::nodes::Node;
+use crate::semantic::SymbolTable;
+use crate::runtime;
+use std::path::Path;
+use std::process::{Command, Stdio};
 
-pub fn interpret(nodes: Vec<Node>, file_name: String){
-    let mut file_name: String = file_name[..file_name.len() - 3].to_string().to_string(); // this is so we remove the ".ch"
-    file_name.push_str(".c");
-    let mut file_data: String = "#include<stdio.h>\n".to_owned();
+// what is built instead of a program, for the C code calling the 'export' functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Library{
+    Static, // "libname.a"
+    Shared, // "libname.so"
+}
+
+// the C compilers chal is known to work with, the first one installed is used unless another is chosen
+pub const COMPILERS: [&str; 4] = ["gcc", "clang", "cc", "tcc"];
+
+// how the generated C is compiled - "--cc clang -O2 --cflags '-Wall -g'"
+#[derive(Debug, Clone, Default)]
+pub struct Compiler{
+    pub command: Option<String>, // from "--cc" or $CC, which may carry arguments like "gcc -m64"
+    pub optimization: Option<String>, // "-O0" to "-O3" or "-Os"
+    pub flags: Vec<String>,
+}
+
+impl Compiler{
+    fn command(&self) -> Result<Command, String>{
+        if let Some(command) = &self.command {
+            // "gcc -m64" runs gcc with "-m64" before the other arguments
+            let mut words = command.split_whitespace();
+            let program: &str = words.next().ok_or("'--cc' and $CC cannot be empty".to_string())?;
+            let mut command = Command::new(program);
+            command.args(words);
+            return Ok(command);
+        }
+        for command in COMPILERS{
+            let installed = Command::new(command).arg("-v").stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok();
+            if installed {return Ok(Command::new(command));}
+        }
+        return Err(format!("no C compiler was found: install one of {} or choose one with '--cc' or $CC", COMPILERS.join(", ")));
+    }
+}
+
+// runs a step of the build, a failed one stops chal
+fn run(command: &mut Command, file_name: &String) -> Result<(), String>{
+    let program: String = command.get_program().to_string_lossy().to_string();
+    match command.status(){
+        Ok(status) if status.success() => return Ok(()),
+        Ok(_status) => return Err(format!("'{}' could not build {}", program, file_name)),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!("'{}' was not found: make sure it is installed and in the PATH", program));
+        },
+        Err(error) => return Err(format!("could not run '{}': {}", program, error)),
+    }
+}
+
+// the header of a library - the types and the prototypes of its exported functions
+fn header(nodes: &Vec<Node>, table: &SymbolTable, name: &str) -> String{
+    let guard: String = name.chars().map(|c| if c.is_ascii_alphanumeric() {c.to_ascii_uppercase()} else {'_'}).collect();
+    let mut result: String = format!("// generated by chal from {}.ch\n#ifndef {}_H\n#define {}_H\n\n", name, guard, guard);
+    result.push_str("typedef char* str;\n");
+    result.push_str(&runtime::type_declarations_of(&table.exported_types));
+    result.push_str("\n");
+    for node in nodes{
+        if let Node::FunctionDefinition(function) = node{
+            if function.is_exported() {result.push_str(&function.prototype());}
+        }
+    }
+    result.push_str("\n#endif\n");
+    return result;
+}
+
+// the release builds leave out the runtime checks, like the array bounds checks
+pub fn interpret(nodes: Vec<Node>, table: &SymbolTable, file_name: String, release: bool, library: Option<Library>, compiler: &Compiler) -> Result<(), String>{
+    // "prog.ch" becomes "prog.c", whatever the extension of the main module is
+    let stem: String = Path::new(&file_name).with_extension("").to_string_lossy().to_string();
+    let file_name: String = format!("{}.c", stem);
+    let mut file_data: String = String::new();
+    // a library only shares its exported functions, everything else is 'static' - the prelude included
+    if library.is_some() {file_data.push_str("#define __CHAL_LOCAL static __attribute__((unused))\n");}
+    file_data.push_str(&runtime::prelude());
+    // the headers of the 'extern' functions - "<stdio.h>" or a path from the generated file
+    for header in &table.headers{
+        if header.starts_with('<') {file_data.push_str(&format!("#include {}\n", header));}
+        else {file_data.push_str(&format!("#include \"{}\"\n", header));}
+    }
 
-    for i in nodes{
+    // the types used by the code are only known once it is generated - the function prototypes
+    // and the globals come first, so every function can use them (and the globals the functions),
+    // the same goes for the functions the compiler makes, like the lifted lambdas
+    let mut prototypes: String = String::new();
+    let mut code: String = String::new();
+    let (globals, definitions): (Vec<Node>, Vec<Node>) = nodes.into_iter().partition(|node| matches!(node, Node::Global(_)));
+    for i in &definitions{
+        if let Node::FunctionDefinition(function) = i {prototypes.push_str(&function.prototype());}
+    }
+    for i in &globals{
+        prototypes.push_str(&i.to_c());
+    }
+    for i in &definitions{
         //interpret_function(&i, &file_name);
-        file_data.push_str(&i.to_c().to_owned());
+        code.push_str(&i.to_c().to_owned());
     }
-    std::fs::write(&file_name, file_data).expect("Unable to crate file.");
+    file_data.push_str(&runtime::type_declarations());
+    file_data.push_str(&runtime::function_prototypes());
+    file_data.push_str(&prototypes);
+    file_data.push_str(&runtime::function_definitions());
+    file_data.push_str(&code);
+    std::fs::write(&file_name, file_data).map_err(|error| format!("could not write {}: {}", file_name, error))?;
 
-    std::process::Command::new("gcc").arg(&file_name).spawn().unwrap().wait().expect("Could not compile source code with GCC: make sure GCC is installed on your machine.\n");
+    // "lib/geometry.ch" becomes "lib/geometry.h" and "lib/libgeometry.a"
+    let path = Path::new(&stem);
+    let name: String = path.file_name().unwrap().to_string_lossy().to_string();
+    let output = |extension: &str| path.with_file_name(format!("lib{}.{}", name, extension)).to_string_lossy().to_string();
+    if library.is_some(){
+        let header_name: String = format!("{}.h", stem);
+        std::fs::write(&header_name, header(&definitions, table, &name)).map_err(|error| format!("could not write {}: {}", header_name, error))?;
+    }
+
+    let mut command = compiler.command()?;
+    command.arg(&file_name);
+    match library{
+        // the object file is archived once it is compiled
+        Some(Library::Static) => {command.args(["-c", "-o", &format!("{}.o", stem)]);},
+        Some(Library::Shared) => {command.args(["-shared", "-fPIC", "-o", &output("so")]);},
+        None => (),
+    }
+    if release {command.arg("-DNDEBUG");}
+    if let Some(optimization) = &compiler.optimization {command.arg(optimization);}
+    command.args(&compiler.flags);
+    for library in &table.libraries{
+        command.arg(format!("-l{}", library));
+    }
+    run(&mut command, &file_name)?;
+    if library == Some(Library::Static){
+        run(Command::new("ar").args(["rcs", &output("a"), &format!("{}.o", stem)]), &file_name)?;
+        std::fs::remove_file(format!("{}.o", stem)).ok();
+    }
     //std::fs::remove_file(file_name).expect("Could not delete source file (<filename>.c).\n");
+    return Ok(());
 }
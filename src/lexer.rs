@@ -15,6 +15,13 @@ pub enum Keyword{
     F32,
     F64,
     Str,
+    Bool,
+    List,
+    Struct,
+    Const,
+    Enum,
+    Match,
+    Case,
     If,
     Elif,
     Else,
@@ -23,8 +30,15 @@ pub enum Keyword{
     While,
     End,
     Fn,
-    Return
-    // Continue and Break 
+    Return,
+    Import,
+    Use,
+    In,
+    Break,
+    Continue,
+    Defer,
+    Extern,
+    Export,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -63,15 +77,22 @@ pub enum Token{
     DivEq,    // /=
     ModEq,    // %=
     Colon,    // :
+    Comma,    // ,
+    Dot,      // .
+    Semicolon, // ;
+    LBracket, // [
+    RBracket, // ]
     Return,   // ->
+    Question, // ?
     NewLine,  // \n
     And,      // &&
     Or,       // ||
     Not,      // !        |
-    // Reference // &     | unary operators
-    // Dereference // *   |
+    Reference,   // &     | unary operators
+    Dereference, // *     | (lexed as Mul, the expression parser tells the two apart)
     Keyword(Keyword),
-    Identifier(String)
+    Identifier(String),
+    CCode(String), // the lines of a "c:" block, copied to the generated C as they are
     /*
     Token_CHAR, // to add string token
     */
@@ -110,6 +131,7 @@ fn to_digit(s: &str) -> Token{
 }
 
 fn is_float(s: &str) -> bool{
+    if !s.chars().any(|c| c.is_ascii_digit()) {return false;}
     let mut has_dot: bool = false;
     for i in s.chars(){
         match i{
@@ -147,6 +169,7 @@ pub fn lexer(src_code: &str) -> Vec<Token>{
         ("f32", Keyword::F32),
         ("f64", Keyword::F64),
         ("str", Keyword::Str),
+        ("bool", Keyword::Bool),
         ("if", Keyword::If),
         ("elif", Keyword::Elif),
         ("else", Keyword::Else),
@@ -156,11 +179,37 @@ pub fn lexer(src_code: &str) -> Vec<Token>{
         ("end", Keyword::End),
         ("fn", Keyword::Fn),
         ("return", Keyword::Return),
+        ("import", Keyword::Import),
+        ("use", Keyword::Use),
+        ("in", Keyword::In),
+        ("list", Keyword::List),
+        ("struct", Keyword::Struct),
+        ("const", Keyword::Const),
+        ("enum", Keyword::Enum),
+        ("match", Keyword::Match),
+        ("case", Keyword::Case),
+        ("break", Keyword::Break),
+        ("continue", Keyword::Continue),
+        ("defer", Keyword::Defer),
+        ("extern", Keyword::Extern),
+        ("export", Keyword::Export),
     ]);
 
-    let re = regex::Regex::new(r#"(#.*)|(\n)|(".+")|(\*\*)|(//)|(->)|(&&)|(\|\|)|([!&\*])|([=!<>\+\-\*/%]=)|(\d+(\.\d*)*)|([a-zA-Z0-9\-_]+)|[\(\):=\+\-\*/<>\#%]"#).unwrap();
+    let re = regex::Regex::new(r#"(#.*)|("(?:[^"\\]|\\.)*")|(\*\*)|(//)|(->)|(&&)|(\|\|)|([=!<>\+\-\*/%]=)|(\d+(\.\d*)?)|([a-zA-Z_][a-zA-Z0-9_]*)|[\(\)\[\]:;,\.=\+\-\*/<>%!&\?]"#).unwrap();
 
+    // the lines between "c(inputs -> outputs):" and its "end" are C, so they are not tokenized
+    let mut c_code: Option<String> = None;
     for line in lines{
+        if let Some(code) = c_code.as_mut(){
+            if line.trim() != "end"{
+                code.push_str(line.trim_end());
+                code.push('\n');
+                continue;
+            }
+            result.push(Token::CCode(c_code.take().unwrap()));
+            result.push(Token::NewLine);
+        }
+        let start: usize = result.len();
         for matches in re.captures_iter(line){
             let token: &str = &matches[0];
             if token.is_empty() {continue;}
@@ -212,14 +261,28 @@ pub fn lexer(src_code: &str) -> Vec<Token>{
                 "/=" => result.push(Token::DivEq),
                 "%=" => result.push(Token::ModEq),
                 ":"  => result.push(Token::Colon),
+                ","  => result.push(Token::Comma),
+                "."  => result.push(Token::Dot),
+                ";"  => result.push(Token::Semicolon),
+                "["  => result.push(Token::LBracket),
+                "]"  => result.push(Token::RBracket),
                 "->" => result.push(Token::Return),
                 "&&" => result.push(Token::And),
                 "||" => result.push(Token::Or),
                 "!"  => result.push(Token::Not),
+                "&"  => result.push(Token::Reference),
+                "?"  => result.push(Token::Question),
                 _    => result.push(Token::Identifier(token.to_string())),
             }
         }
+        if opens_c_block(&result[start..]) {c_code = Some(String::new());}
         result.push(Token::NewLine);
     }
     return result;
 }
+
+// "c:" or "c(x, y -> z):" on a line of its own
+fn opens_c_block(line: &[Token]) -> bool{
+    if line.first() != Some(&Token::Identifier("c".to_string())) || line.last() != Some(&Token::Colon) {return false;}
+    return line.len() == 2 || (line[1] == Token::LPar && line[line.len() - 2] == Token::RPar);
+}
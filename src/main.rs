@@ -3,27 +3,93 @@ mod parser;
 mod interpreter;
 mod nodes;
 mod stack;
+mod semantic;
+mod runtime;
+mod builtins;
+mod modules;
+
+use std::path::PathBuf;
+
+fn fail(error: String) -> !{
+    eprintln!("error: {}", error);
+    std::process::exit(1);
+}
 
 fn main() {
-    /*
     let arguments: Vec<String> = std::env::args().collect();
-    let file_name: &str = &arguments[1];
-    let source_code = std::fs::read_to_string(file_name).expect("File not found!");
-    */
-    let source_code = std::fs::read_to_string("1.ch").expect("File not found!");
-    println!("{}", source_code);
-    let tokens = lexer::lexer(&source_code);
-    /*
-    for i in &tokens{
-        println!("{:?}", i);
+    let mut file_name: Option<String> = None;
+    // the directories where imported modules are searched for, after the importing file's own
+    let mut search_path: Vec<PathBuf> = Vec::new();
+    let mut release: bool = false;
+    let mut libraries: Vec<String> = Vec::new(); // linked to the program, for the 'extern' functions
+    let mut library: Option<interpreter::Library> = None; // built instead of a program
+    let mut compiler = interpreter::Compiler::default();
+
+    let mut i = 1;
+    while i < arguments.len(){
+        match arguments[i].as_str(){
+            "-I" => {
+                i += 1;
+                match arguments.get(i){
+                    Some(directory) => search_path.push(PathBuf::from(directory)),
+                    None => fail("'-I' expects a directory".to_string()),
+                }
+            },
+            "-l" | "--link" => {
+                i += 1;
+                match arguments.get(i){
+                    Some(library) => libraries.push(library.to_string()),
+                    None => fail(format!("'{}' expects the name of a library", arguments[i - 1])),
+                }
+            },
+            "--lib" => {
+                i += 1;
+                match arguments.get(i).map(|kind| kind.as_str()){
+                    Some("static") => library = Some(interpreter::Library::Static),
+                    Some("shared") => library = Some(interpreter::Library::Shared),
+                    _ => fail("'--lib' expects 'static' or 'shared'".to_string()),
+                }
+            },
+            "--cc" => {
+                i += 1;
+                match arguments.get(i){
+                    Some(command) => compiler.command = Some(command.to_string()),
+                    None => fail(format!("'--cc' expects a C compiler, like {}", interpreter::COMPILERS.join(", "))),
+                }
+            },
+            "--cflags" => {
+                i += 1;
+                match arguments.get(i){
+                    Some(flags) => compiler.flags.extend(flags.split_whitespace().map(|flag| flag.to_string())),
+                    None => fail("'--cflags' expects the flags given to the C compiler".to_string()),
+                }
+            },
+            "-O0" | "-O1" | "-O2" | "-O3" | "-Os" => compiler.optimization = Some(arguments[i].to_string()),
+            argument if argument.starts_with("-O") => fail(format!("unknown optimization level '{}', expected -O0, -O1, -O2, -O3 or -Os", argument)),
+            "--release" => release = true,
+            argument if argument.starts_with("-I") => search_path.push(PathBuf::from(&argument[2..])),
+            argument if argument.starts_with("-l") => libraries.push(argument[2..].to_string()),
+            argument => file_name = Some(argument.to_string()),
+        }
+        i += 1;
     }
-    println!("");
-    */
-    let ast = parser::parse(tokens);
-    /*
-    for i in &ast{
-        println!("{:#?}", i);
+    if let Some(paths) = std::env::var_os("CHAL_PATH"){
+        search_path.extend(std::env::split_paths(&paths));
     }
-    */
-    interpreter::interpret(ast, "1.ch".to_string());
+    if compiler.command.is_none(){
+        compiler.command = std::env::var("CC").ok().filter(|command| !command.trim().is_empty());
+    }
+
+    let file_name: String = file_name.unwrap_or_else(|| fail("usage: chal <file.ch> [--release] [-I <directory>]... [-l <library>]... [--lib static|shared] [--cc <compiler>] [-O<level>] [--cflags <flags>]".to_string()));
+    let mut modules = modules::load_program(&PathBuf::from(&file_name), search_path).unwrap_or_else(|error| fail(error));
+    let mut table = semantic::analyze(&mut modules, library.is_some()).unwrap_or_else(|error| fail(error));
+    for library in &libraries{
+        table.link(library);
+    }
+
+    let ast: Vec<nodes::Node> = modules
+        .into_iter()
+        .flat_map(|module| module.nodes)
+        .collect();
+    interpreter::interpret(ast, &table, file_name, release, library, &compiler).unwrap_or_else(|error| fail(error));
 }
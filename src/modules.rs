@@ -0,0 +1,96 @@
+use crate::lexer;
+use crate::parser;
+use crate::nodes::Node;
+use crate::nodes::imports::ImportSource;
+use std::path::{Path, PathBuf};
+
+// a single source file and the nodes parsed from it
+#[derive(Debug)]
+pub struct Module{
+    pub name: String, // the file name without the ".ch", used to qualify its names
+    pub path: PathBuf,
+    pub nodes: Vec<Node>,
+}
+
+pub struct ModuleLoader{
+    search_path: Vec<PathBuf>,
+    modules: Vec<Module>, // every module comes after the modules it imports
+    loading: Vec<PathBuf>, // the chain of imports which is currently being loaded
+}
+
+fn module_name(path: &Path) -> Result<String, String>{
+    let name: String = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let valid = name.chars().next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_') &&
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {return Err(format!("'{}' is not a valid module name", path.display()));}
+    return Ok(name);
+}
+
+fn file_name(path: &Path) -> String{
+    path.file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+impl ModuleLoader{
+    pub fn new(search_path: Vec<PathBuf>) -> Self{
+        ModuleLoader {search_path: search_path, modules: Vec::new(), loading: Vec::new()}
+    }
+
+    // the imports are looked up relative to the importing file first and then in the search path
+    fn resolve(&self, source: &ImportSource, importer: &Path) -> Result<PathBuf, String>{
+        let relative: PathBuf = match source{
+            ImportSource::Path(path) => PathBuf::from(path),
+            ImportSource::Name(name) => PathBuf::from(format!("{}.ch", name)),
+        };
+        let directory: &Path = importer.parent().unwrap_or(Path::new("."));
+        let candidates = std::iter::once(directory.join(&relative))
+            .chain(self.search_path.iter().map(|directory| directory.join(&relative)));
+        for candidate in candidates{
+            if candidate.is_file() {return Ok(candidate);}
+        }
+        return Err(format!("{}: cannot find module '{}'", file_name(importer), relative.display()));
+    }
+
+    // loads the module with all of its imports and returns its name
+    pub fn load(&mut self, path: &Path) -> Result<String, String>{
+        let path: PathBuf = path.canonicalize().map_err(|_| format!("cannot open '{}'", path.display()))?;
+        if let Some(module) = self.modules.iter().find(|module| module.path == path){
+            return Ok(module.name.to_string());
+        }
+        if let Some(position) = self.loading.iter().position(|loading| *loading == path){
+            let cycle: Vec<String> = self.loading[position..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|path| file_name(path))
+                .collect();
+            return Err(format!("import cycle: {}", cycle.join(" -> ")));
+        }
+
+        // the names of the main module are never qualified, so only the imported modules need a valid name
+        let name = if self.loading.is_empty() {file_name(&path).trim_end_matches(".ch").to_string()} else {module_name(&path)?};
+        if self.modules.iter().any(|module| module.name == name){
+            return Err(format!("there are two different modules named '{}'", name));
+        }
+
+        let source_code = std::fs::read_to_string(&path).map_err(|_| format!("cannot read '{}'", path.display()))?;
+        let mut nodes: Vec<Node> = parser::parse(lexer::lexer(&source_code));
+
+        self.loading.push(path.to_path_buf());
+        for node in nodes.iter_mut(){
+            if let Node::Import(import) = node{
+                let import_path = self.resolve(&import.source, &path)?;
+                import.module = self.load(&import_path)?;
+            }
+        }
+        self.loading.pop();
+
+        self.modules.push(Module {name: name.to_string(), path: path, nodes: nodes});
+        return Ok(name);
+    }
+}
+
+// loads the whole program, the main module being the last one
+pub fn load_program(path: &Path, search_path: Vec<PathBuf>) -> Result<Vec<Module>, String>{
+    let mut loader = ModuleLoader::new(search_path);
+    loader.load(path)?;
+    return Ok(loader.modules);
+}
@@ -0,0 +1,120 @@
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+
+#[derive(Debug, Clone)]
+pub struct NodeArrayLiteral{
+    elements: Vec<Box<Node>>,
+    // set by the semantic pass
+    element_types: Vec<VarType>,
+    var_type: VarType,
+}
+
+impl NodeArrayLiteral{
+    pub fn new(elements: Vec<Box<Node>>) -> Self{
+        NodeArrayLiteral {elements: elements, element_types: Vec::new(), var_type: VarType::None}
+    }
+
+    // a compound literal of the struct wrapping the array - "(__array_i32_3){{1, 2, 3}}",
+    // a list is made by copying such an array to the heap
+    pub fn to_c(&self) -> String{
+        let mut elements: String = String::new();
+        for i in 0..self.elements.len(){
+            elements.push_str(&self.elements[i].to_c());
+            if i != self.elements.len() - 1 {elements.push_str(", ");}
+        }
+
+        if let VarType::List(element_type) = &self.var_type{
+            let list: String = self.var_type.to_c().trim().to_string();
+            return format!("{}_from(({}[]){{{}}}, {})", list, element_type.to_c().trim(), elements, self.elements.len());
+        }
+        return format!("(({}){{{{{}}}}})", self.var_type.to_c().trim(), elements);
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.elements.is_empty(){
+            return Err("the type of an empty array literal cannot be inferred".to_string());
+        }
+        self.element_types = Vec::new();
+        for element in self.elements.iter_mut(){
+            self.element_types.push(element.analyze(table)?);
+        }
+
+        let mut element_type: VarType = self.element_types[0].clone();
+        for var_type in &self.element_types[1..]{
+            element_type = common_type(&element_type, var_type).ok_or(format!(
+                "the elements of an array literal must have the same type, found {} and {}", element_type, var_type
+            ))?;
+        }
+        if element_type == VarType::None{
+            return Err("the elements of an array literal cannot be of type none".to_string());
+        }
+
+        let var_type = VarType::Array(Box::new(element_type), self.elements.len());
+        self.var_type = var_type.clone();
+        return Ok(self.coerce(var_type.clone(), &var_type));
+    }
+
+    // the literal can become an array of the same length or a list, as long as all of
+    // its elements can be converted to the element type
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        let element_type = match target{
+            VarType::Array(element_type, length) if *length == self.elements.len() => element_type,
+            VarType::List(element_type) => element_type,
+            _ => return value_type,
+        };
+        let mut element_types: Vec<VarType> = Vec::new();
+        for i in 0..self.elements.len(){
+            // the literals nested in this one are converted as well
+            element_types.push(self.elements[i].coerce(self.element_types[i].clone(), element_type));
+        }
+        if element_types.iter().any(|var_type| !is_compatible(var_type, element_type)) {return value_type;}
+        self.element_types = element_types;
+        self.var_type = target.clone();
+        return self.var_type.clone();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeIndex{
+    pub array: Box<Node>,
+    index: Box<Node>,
+    array_type: VarType, // set by the semantic pass
+}
+
+impl NodeIndex{
+    pub fn new(array: Node, index: Node) -> Self{
+        NodeIndex {array: Box::new(array), index: Box::new(index), array_type: VarType::None}
+    }
+
+    // the index is checked against the length unless the program is compiled with NDEBUG
+    pub fn to_c(&self) -> String{
+        match &self.array_type{
+            VarType::List(_element) => {
+                return format!("(*{}_at({}, {}))", self.array_type.to_c().trim(), self.array.to_c(), self.index.to_c());
+            },
+            VarType::Array(_element, length) => {
+                return format!("({}).data[__CHAL_INDEX({}, {})]", self.array.to_c(), self.index.to_c(), length);
+            },
+            // the memory of alloc() - its size isn't known, so only the null pointers are caught
+            VarType::Pointer(_element) => {
+                return format!("(({})__CHAL_CHECK_NULL({}))[{}]", self.array_type.to_c(), self.array.to_c(), self.index.to_c());
+            },
+            _ => return String::new(),
+        }
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.array_type = self.array.analyze(table)?;
+        let index_type = self.index.analyze(table)?;
+        if !is_numeric(&index_type) || is_float(&index_type){
+            return Err(format!("an index must be an integer, found {}", index_type));
+        }
+        match &self.array_type{
+            VarType::Array(element_type, _length) => return Ok(*element_type.clone()),
+            VarType::List(element_type)           => return Ok(*element_type.clone()),
+            VarType::Pointer(element_type) if **element_type != VarType::None => return Ok(*element_type.clone()),
+            var_type => return Err(format!("cannot index a value of type {}", var_type)),
+        }
+    }
+}
@@ -1,14 +1,17 @@
 use crate::lexer::*;
 use crate::parser::*;
 use super::Node;
+use super::globals::ConstValue;
+use super::{NodeValueInt, NodeFunctionCall, NodeLambda, NodeWrapper, NodeTry, NodeAlloc, NodeUnaryExpression, NodeVariableCall, NodeArrayLiteral, NodeTupleLiteral, NodeIndex, NodeFieldAccess, get_closing, split_arguments};
 use crate::stack::Stack;
-
-use std::collections::HashMap;
+use crate::semantic::*;
+use crate::builtins;
 
 #[derive(Debug, Clone)]
 pub struct NodeBinaryExpression{
     operands: [Box<Node>;2],
     operator: OperatorType,
+    functions: bool, // set by the semantic pass when two closures are compared
 }
 
 impl NodeBinaryExpression{
@@ -16,25 +19,86 @@ impl NodeBinaryExpression{
         NodeBinaryExpression {
             operands: [Box::new(Node::new()), Box::new(Node::new())], 
             operator: OperatorType::new(),
+            functions: false,
         }
     }
 
+    pub fn fold(&self) -> Option<ConstValue>{
+        if self.operator.is_assignment() {return None;}
+        return ConstValue::binary(&self.operator, self.operands[0].fold()?, self.operands[1].fold()?);
+    }
+
     pub fn to_c(&self) -> String{
+        // the closures are the same when they call the same C function
+        let field: &str = if self.functions {".call"} else {""};
         let mut result: String = "(".to_owned();
         result.push_str(&self.operands[0].to_c().to_owned());
+        result.push_str(field);
         result.push_str(" ");
         result.push_str(&self.operator.to_c().to_owned());
         result.push_str(&self.operands[1].to_c().to_owned());
+        result.push_str(field);
         result.push_str(")");
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let left = self.operands[0].analyze(table)?;
+        let mut right = self.operands[1].analyze(table)?;
+        let operator = self.operator.to_c().trim().to_string();
+
+        if self.operator.is_assignment(){
+            if !self.operands[0].is_lvalue(){
+                return Err(format!("the left side of '{}' is not assignable", operator));
+            }
+            right = self.operands[1].coerce(right, &left);
+            if !is_compatible(&right, &left){
+                return Err(format!("cannot assign a value of type {} to a variable of type {}", right, left));
+            }
+            if !matches!(self.operator, OperatorType::Eq) && !is_numeric(&left){
+                return Err(format!("cannot apply '{}' to a value of type {}", operator, left));
+            }
+            return Ok(left);
+        }
+
+        // the variants of the plain enums are C enum constants, so they are compared directly
+        let comparison: bool = matches!(self.operator, OperatorType::EqEq | OperatorType::NotEq);
+        if comparison && left == right && table.is_plain_enum(&left) {return Ok(VarType::Bool);}
+
+        // the bools can only be compared and combined
+        let logical: bool = matches!(self.operator, OperatorType::And | OperatorType::Or);
+        if (comparison && left == VarType::Bool && right == VarType::Bool) || (logical && is_condition(&left) && is_condition(&right)){
+            return Ok(VarType::Bool);
+        }
+
+        // the pointers can only be compared - "p != null"
+        let pointers = is_pointer(&left) || is_pointer(&right);
+        if comparison && pointers && (is_compatible(&left, &right) || is_compatible(&right, &left)){
+            if let Some(function_type) = [&left, &right].into_iter().find(|var_type| matches!(var_type, VarType::Function(_, _))).cloned(){
+                self.operands[0].coerce(left.clone(), &function_type);
+                self.operands[1].coerce(right.clone(), &function_type);
+                self.functions = true;
+            }
+            return Ok(VarType::Bool);
+        }
+        if pointers && matches!(self.operator, OperatorType::Plus | OperatorType::Minus){
+            return Err(format!("cannot apply '{}' to values of type {} and {}, pointer arithmetic is not allowed", operator, left, right));
+        }
+
+        if !is_numeric(&left) || !is_numeric(&right){
+            return Err(format!("cannot apply '{}' to values of type {} and {}", operator, left, right));
+        }
+        if self.operator.is_logical() {return Ok(VarType::Bool);}
+        return Ok(wider_numeric(&left, &right));
+    }
 }
 
 impl From<&Vec<Token>> for NodeBinaryExpression{
     fn from(tokens: &Vec<Token>) -> Self{
         NodeBinaryExpression {
             operands: [Box::new(Node::from(&tokens[0])), Box::new(Node::from(&tokens[2]))], 
-            operator: OperatorType::from(&tokens[1])
+            operator: OperatorType::from(&tokens[1]),
+            functions: false,
         }
     }
 }
@@ -43,34 +107,47 @@ impl Token{
     fn precedence(&self) -> u8{
         match *self{
             Token::Eq       => 1,
-            Token::Or       => 1,
-            Token::And      => 2,
-            Token::EqEq     => 3,
-            Token::NotEq    => 3,
-            Token::Lt       => 3,
-            Token::LtEq     => 3,
-            Token::Gt       => 3,
-            Token::GtEq     => 3,
-            Token::Plus     => 4,
-            Token::PlusEq   => 4,
-            Token::Minus    => 4,
-            Token::MinusEq  => 4,
-            Token::Mul      => 5,
-            Token::MulEq    => 5,
-            Token::Div      => 5,
-            Token::DivEq    => 5,
-            Token::Mod      => 5,
-            Token::ModEq    => 5,
-            Token::Exp      => 6,
-            Token::FloorDiv => 6,
+            Token::PlusEq   => 1,
+            Token::MinusEq  => 1,
+            Token::MulEq    => 1,
+            Token::DivEq    => 1,
+            Token::ModEq    => 1,
+            Token::Or       => 2,
+            Token::And      => 3,
+            Token::EqEq     => 4,
+            Token::NotEq    => 4,
+            Token::Lt       => 4,
+            Token::LtEq     => 4,
+            Token::Gt       => 4,
+            Token::GtEq     => 4,
+            Token::Plus     => 5,
+            Token::Minus    => 5,
+            Token::Mul      => 6,
+            Token::Div      => 6,
+            Token::Mod      => 6,
+            Token::Exp      => 7,
+            Token::FloorDiv => 7,
             Token::RPar     => 0,
             _ => 0
         }
     }
+
+    fn is_right_associative(&self) -> bool{
+        match *self{
+            Token::Eq      => return true,
+            Token::PlusEq  => return true,
+            Token::MinusEq => return true,
+            Token::MulEq   => return true,
+            Token::DivEq   => return true,
+            Token::ModEq   => return true,
+            Token::Exp     => return true,
+            _ => return false,
+        }
+    }
 }
 
-fn is_arithmetic(token: &Token) -> bool{
-    // returns true if this is a keyword or a non-string value
+fn is_operand(token: &Token) -> bool{
+    // returns true if this is an identifier or a value
     match token{
         Token::Identifier(_val) => return true,
         Token::Int8(_val)    => return true,
@@ -83,108 +160,259 @@ fn is_arithmetic(token: &Token) -> bool{
         Token::UInt64(_val)  => return true,
         Token::Float32(_val) => return true,
         Token::Float64(_val) => return true,
-        // Token::String(_val)  => return true,
+        Token::String(_val)  => return true,
         _ => return false,
     }
 }
 
-// an adaptation of the Shunting-yard algorithm for infix notation
-pub fn generate_binary_expression_tree(tokens: &Vec<Token>) -> NodeBinaryExpression{
-    let mut st_c: Stack<Token> = Stack::<Token>::new(); // character (Token) stack 
-    let mut st_n: Stack<Node> = Stack::<Node>::new(); // node stack
-    for i in tokens{
-        if *i == Token::LPar {
-            st_c.insert(i.clone());
-
-        }else if is_arithmetic(i){
-            st_n.insert(Node::from(i));
-
-        }else if i.precedence() > 0{
-            while !st_c.empty() &&
-                  *st_c.top() != Token::LPar &&
-                  ((*i != Token::Exp && st_c.top().precedence() >= i.precedence()) ||
-                  (*i == Token::Exp && st_c.top().precedence() > i.precedence())){
-                
-                let operator: OperatorType = OperatorType::from(&st_c.pop());
-                let operand2: Node = st_n.pop();
-                let operand1: Node = st_n.pop();
-                let mut temp: NodeBinaryExpression = NodeBinaryExpression::new();
-
-                temp.operands[0] = Box::new(operand1);
-                temp.operands[1] = Box::new(operand2);
-                temp.operator = operator;
-                st_n.insert(Node::BinaryExpression(temp));
-            }
-            st_c.insert(i.clone());
-
-        }else if *i == Token::RPar{
-            while !st_c.empty() && *st_c.top() != Token::LPar {
-                let operator: OperatorType = OperatorType::from(&st_c.pop());
-                let operand2: Node = st_n.pop();
-                let operand1: Node = st_n.pop();
-                let mut temp: NodeBinaryExpression = NodeBinaryExpression::new();
-
-                temp.operands[0] = Box::new(operand1);
-                temp.operands[1] = Box::new(operand2);
-                temp.operator = operator;
-                st_n.insert(Node::BinaryExpression(temp));
-            }
- 
-            let _remove = st_c.pop();
-        }
-    }
+// the parentheses holding a comma are a tuple - "(q, r)"
+fn is_tuple_literal(tokens: &[Token]) -> bool{
+    if tokens[0] != Token::LPar {return false;}
+    return split_arguments(&tokens[1 .. get_closing(tokens)]).len() > 1;
+}
 
-    while !st_c.empty() && *st_c.top() != Token::LPar {
-        let operator: OperatorType = OperatorType::from(&st_c.pop());
-        let operand2: Node = st_n.pop();
-        let operand1: Node = st_n.pop();
-        let mut temp: NodeBinaryExpression = NodeBinaryExpression::new();
+// the entries of the operator stack - unary operators only take a single operand
+#[derive(Debug, Clone, PartialEq)]
+enum StackEntry{
+    LPar,
+    Binary(Token),
+    Unary(Token),
+}
 
-        temp.operands[0] = Box::new(operand1);
-        temp.operands[1] = Box::new(operand2);
-        temp.operator = operator;
-        st_n.insert(Node::BinaryExpression(temp));
+// takes the needed operands from the node stack and pushes back the resulting expression
+fn reduce(st_n: &mut Stack<Node>, entry: StackEntry) -> Result<(), String>{
+    let missing: Option<&Token> = match &entry{
+        StackEntry::Binary(token) if st_n.len() < 2 => Some(token),
+        StackEntry::Unary(token) if st_n.empty() => Some(token),
+        _ => None,
+    };
+    if let Some(token) = missing{
+        return Err(format!("'{}' is missing a value", OperatorType::from(token).to_c().trim()));
     }
+    match entry{
+        StackEntry::Binary(token) => {
+            let operand2: Node = st_n.pop();
+            let operand1: Node = st_n.pop();
+            let mut temp: NodeBinaryExpression = NodeBinaryExpression::new();
 
-    let result: NodeBinaryExpression = match st_n.top(){
-        Node::BinaryExpression(val) => val.clone(),
-        _ => NodeBinaryExpression::new(),
-    };
-    return result;
+            temp.operands[0] = Box::new(operand1);
+            temp.operands[1] = Box::new(operand2);
+            temp.operator = OperatorType::from(&token);
+            st_n.insert(Node::BinaryExpression(temp));
+        },
+        StackEntry::Unary(token) => {
+            let operand: Node = st_n.pop();
+            // "-3" is a signed literal of the smallest type it fits in, as the number itself is lexed as unsigned
+            if let (Token::Minus, Node::ValueUInt(literal)) = (&token, &operand){
+                let value: i128 = -(literal.value as i128);
+                let var_type: VarType = match value{
+                    -128 ..= 0 => VarType::I8,
+                    -32_768 ..= -129 => VarType::I16,
+                    -2_147_483_648 ..= -32_769 => VarType::I32,
+                    _ if value >= i64::MIN as i128 => VarType::I64,
+                    _ => panic!("the literal {} does not fit in an i64", value),
+                };
+                st_n.insert(Node::ValueInt(NodeValueInt::new(value as i64, var_type)));
+                return Ok(());
+            }
+            st_n.insert(Node::UnaryExpression(NodeUnaryExpression::new(operand, OperatorType::from(&token))));
+        },
+        StackEntry::LPar => (),
+    }
+    return Ok(());
 }
 
-fn greater_var_type(type1: &VarType, type2: &VarType) -> VarType{
-    let values: HashMap<VarType, i8> = HashMap::from([
-        (VarType::None, 0),
-        (VarType::I8, 1),
-        (VarType::I16, 3),
-        (VarType::I32, 5),
-        (VarType::I64, 8),
-        (VarType::U8, 2),
-        (VarType::U16, 4),
-        (VarType::U32, 6),
-        (VarType::U64, 9),
-        (VarType::F32, 7),
-        (VarType::F64, 10),
-    ]);
-    if values.get(type1) > values.get(type2){
-        return type1.clone();
+// indexing, field access, calls and '?' bind tighter than any operator, so they are applied to the operand right away
+fn parse_postfix(tokens: &Vec<Token>, i: &mut usize, st_n: &mut Stack<Node>){
+    while *i + 1 < tokens.len(){
+        if tokens[*i + 1] == Token::Question{
+            let value: Node = st_n.pop();
+            st_n.insert(Node::Try(NodeTry::new(value)));
+            *i += 1;
+        }else if tokens[*i + 1] == Token::LBracket{
+            let closing = *i + 1 + get_closing(&tokens[*i + 1 ..]);
+            let index: Node = generate_expression(&tokens[*i + 2 .. closing].to_vec());
+            let array: Node = st_n.pop();
+            st_n.insert(Node::Index(NodeIndex::new(array, index)));
+            *i = closing;
+        }else if let (Token::Dot, Some(Token::Identifier(field))) = (&tokens[*i + 1], tokens.get(*i + 2)){
+            let object: Node = st_n.pop();
+            st_n.insert(Node::FieldAccess(NodeFieldAccess::new(object, field.to_string())));
+            *i += 2;
+        }else if tokens[*i + 1] == Token::LPar{
+            // calling the function a value holds - "handlers[0](x)"
+            let closing = *i + 1 + get_closing(&tokens[*i + 1 ..]);
+            let args: Vec<Box<Node>> = split_arguments(&tokens[*i + 2 .. closing])
+                .iter()
+                .map(|arg| Box::new(generate_expression(arg)))
+                .collect();
+            let callee: Node = st_n.pop();
+            st_n.insert(Node::FunctionCall(NodeFunctionCall::indirect(callee, args)));
+            *i = closing;
+        }else{
+            break;
+        }
     }
-    return type2.clone();
 }
 
-// this is for evaluating the type when using the 'auto' keyword
-pub fn evaluate_var_type(root: &Node) -> VarType{
-    if let Node::BinaryExpression(val) = root{
-        let left = evaluate_var_type(&val.operands[0]);
-        let right = evaluate_var_type(&val.operands[1]);
-        return greater_var_type(&left, &right);
+pub fn generate_expression(tokens: &Vec<Token>) -> Node{
+    return try_generate_expression(tokens).unwrap_or_else(|error| panic!("{}", error));
+}
+
+// an adaptation of the Shunting-yard algorithm for infix notation - the values missing around the
+// operators are errors, so the expressions in the strings can report them
+pub fn try_generate_expression(tokens: &Vec<Token>) -> Result<Node, String>{
+    let mut st_c: Stack<StackEntry> = Stack::<StackEntry>::new(); // operator stack
+    let mut st_n: Stack<Node> = Stack::<Node>::new(); // node stack
+    let mut expects_operand = true; // used to tell apart unary and binary operators
+    let mut i = 0;
+    while i < tokens.len(){
+        let token = &tokens[i];
+        if expects_operand && is_tuple_literal(&tokens[i..]){
+            let closing = i + get_closing(&tokens[i..]);
+            let elements: Vec<Box<Node>> = split_arguments(&tokens[i + 1 .. closing])
+                .iter()
+                .map(|element| try_generate_expression(element).map(Box::new))
+                .collect::<Result<_, String>>()?;
+            st_n.insert(Node::TupleLiteral(NodeTupleLiteral::new(elements)));
+            i = closing;
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if *token == Token::LPar {
+            st_c.insert(StackEntry::LPar);
+
+        }else if expects_operand && (*token == Token::Minus || *token == Token::Not || *token == Token::Reference){
+            st_c.insert(StackEntry::Unary(token.clone()));
+
+        }else if expects_operand && *token == Token::Mul{
+            st_c.insert(StackEntry::Unary(Token::Dereference));
+
+        }else if expects_operand && *token == Token::Exp{
+            // "**p" is lexed as the exponent operator
+            st_c.insert(StackEntry::Unary(Token::Dereference));
+            st_c.insert(StackEntry::Unary(Token::Dereference));
+
+        }else if expects_operand && *token == Token::LBracket{
+            let closing = i + get_closing(&tokens[i..]);
+            let elements: Vec<Box<Node>> = split_arguments(&tokens[i + 1 .. closing])
+                .iter()
+                .map(|element| try_generate_expression(element).map(Box::new))
+                .collect::<Result<_, String>>()?;
+            st_n.insert(Node::ArrayLiteral(NodeArrayLiteral::new(elements)));
+            i = closing;
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if expects_operand && *token == Token::Keyword(Keyword::Fn){
+            // a lambda is a single operand, up to its 'end'
+            let mut end = i;
+            let mut openings = 0;
+            loop{
+                match tokens.get(end){
+                    Some(Token::Keyword(Keyword::End)) if openings == 1 => break,
+                    Some(Token::Keyword(Keyword::End)) => openings -= 1,
+                    Some(_) if opens_block(tokens, end) => openings += 1,
+                    Some(_) => (),
+                    None => panic!("expected 'end' after the body of the lambda"),
+                }
+                end += 1;
+            }
+            st_n.insert(Node::Lambda(NodeLambda::from(&tokens[i ..= end].to_vec())));
+            i = end;
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if expects_operand && *token == Token::Keyword(Keyword::None){
+            st_n.insert(Node::Wrapper(NodeWrapper::new("none", None)));
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if is_operand(token){
+            // the names from other modules are qualified - "module.name"
+            let mut name: String = String::new();
+            if let Token::Identifier(val) = token{
+                name = val.to_string();
+                while i + 2 < tokens.len() && tokens[i + 1] == Token::Dot{
+                    match &tokens[i + 2]{
+                        Token::Identifier(val) => name = format!("{}.{}", name, val),
+                        _ => break,
+                    }
+                    i += 2;
+                }
+            }
+
+            if i + 1 < tokens.len() && tokens[i + 1] == Token::LPar{
+                // this is a function call, so the whole argument list becomes a single operand
+                let closing = i + 1 + get_closing(&tokens[i + 1 ..]);
+                let mut args: Vec<Box<Node>> = Vec::new();
+                let mut arg_names: Vec<Option<String>> = Vec::new();
+                for arg in split_arguments(&tokens[i + 2 .. closing]){
+                    // the named arguments - "name: value" or "name=value"
+                    match (&arg[0], arg.get(1)){
+                        (Token::Identifier(arg_name), Some(Token::Colon | Token::Eq)) => {
+                            arg_names.push(Some(arg_name.to_string()));
+                            args.push(Box::new(try_generate_expression(&arg[2..].to_vec())?));
+                        },
+                        _ => {
+                            arg_names.push(None);
+                            args.push(Box::new(try_generate_expression(&arg)?));
+                        },
+                    }
+                }
+                if name == "alloc"{
+                    if args.len() != 1 {panic!("'alloc' takes the number of values to allocate");}
+                    st_n.insert(Node::Alloc(NodeAlloc::new(*args.pop().unwrap())));
+                }else if builtins::CONSTRUCTORS.contains(&name.as_str()){
+                    // "some(x)", "ok(x)" and "err(e)" - "ok()" for a result without a value
+                    if args.len() > 1 {panic!("'{}' takes a single value", name);}
+                    st_n.insert(Node::Wrapper(NodeWrapper::new(&name, args.pop())));
+                }else{
+                    st_n.insert(Node::FunctionCall(NodeFunctionCall::new(name, args, arg_names)));
+                }
+                i = closing;
+            }else if name.contains('.'){
+                st_n.insert(Node::VariableCall(NodeVariableCall::new(name)));
+            }else{
+                st_n.insert(Node::from(token));
+            }
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if *token == Token::RPar{
+            while !st_c.empty() && *st_c.top() != StackEntry::LPar {
+                let entry = st_c.pop();
+                reduce(&mut st_n, entry)?;
+            }
+            if st_c.empty() {return Err("unmatched ')'".to_string());}
+            let _remove = st_c.pop();
+            parse_postfix(tokens, &mut i, &mut st_n);
+            expects_operand = false;
+
+        }else if token.precedence() > 0{
+            loop{
+                if st_c.empty() {break;}
+                let pops = match st_c.top(){
+                    StackEntry::LPar => false,
+                    StackEntry::Unary(_) => true,
+                    StackEntry::Binary(top) => top.precedence() > token.precedence() ||
+                        (top.precedence() == token.precedence() && !token.is_right_associative()),
+                };
+                if !pops {break;}
+                let entry = st_c.pop();
+                reduce(&mut st_n, entry)?;
+            }
+            st_c.insert(StackEntry::Binary(token.clone()));
+            expects_operand = true;
+        }
+        i += 1;
     }
-    match root{
-        Node::ValueInt(val) => return val.var_type,
-        Node::ValueUInt(val) => return val.var_type,
-        Node::ValueFloat(val) => return val.var_type,
-        Node::ValueString(val) => return val.var_type,
-        _ => return VarType::None,
+
+    while !st_c.empty() {
+        let entry = st_c.pop();
+        reduce(&mut st_n, entry)?;
     }
+    if st_n.len() != 1 {return Err("expected an operator between the values".to_string());}
+    return Ok(st_n.pop());
 }
+
@@ -1,16 +1,90 @@
-use super::Node;
+use super::{Node, body_to_c, cleanup_to_c};
 use crate::lexer::*;
 use crate::parser::*;
+use crate::semantic::*;
 use super::binary_expression::*;
+use super::variables::NodeVariableCall;
+use super::globals::ConstValue;
 
 fn create_condition(tokens: Vec<Token>) -> Box<Node>{
     let binary_expression: bool = tokens
         .iter()
         .any(|token| *token == Token::And || *token == Token::Or);
-    if binary_expression {return Box::new(Node::BinaryExpression(generate_binary_expression_tree(&tokens)));}
+    if binary_expression {return Box::new(generate_expression(&tokens));}
     return Box::new(Node::from(tokens));
 }
 
+fn analyze_condition(condition: &mut Box<Node>, table: &mut SymbolTable) -> Result<(), String>{
+    let condition_type = condition.analyze(table)?;
+    if !is_condition(&condition_type){
+        return Err(format!("a condition must be numeric or bool, found {}", condition_type));
+    }
+    return Ok(());
+}
+
+fn analyze_body(body: &mut Vec<Box<Node>>, table: &mut SymbolTable) -> Result<(), String>{
+    table.push_scope();
+    for node in body.iter_mut(){
+        node.analyze(table)?;
+    }
+    table.pop_scope();
+    return Ok(());
+}
+
+// whether every path through the statements ends in a return, so the code after them never runs
+pub fn always_returns(body: &Vec<Box<Node>>) -> bool{
+    return body.iter().any(|node| returns(node));
+}
+
+fn returns(node: &Node) -> bool{
+    match node{
+        Node::Return(_) => return true,
+        Node::IfStatement(val)   => return always_returns(&val.body) && val.else_statement.as_ref().is_some_and(|node| returns(node)),
+        Node::ElifStatement(val) => return always_returns(&val.body) && val.else_statement.as_ref().is_some_and(|node| returns(node)),
+        Node::ElseStatement(val) => return always_returns(&val.body),
+        // a match over values may have none of its cases taken
+        Node::Match(val) => {
+            let exhaustive = val.arms.iter().all(|arm| !matches!(arm.pattern, Pattern::Value(_))) ||
+                val.arms.iter().any(|arm| matches!(arm.pattern, Pattern::Wildcard));
+            return exhaustive && val.arms.iter().all(|arm| always_returns(&arm.body));
+        },
+        // "while true:" is only left by a break
+        Node::WhileLoop(val) => {
+            let endless = matches!(val.condition.fold(), Some(ConstValue::Int(value)) if value != 0);
+            return endless && !breaks(&val.body);
+        },
+        _ => return false,
+    }
+}
+
+// whether the statements break out of the loop they are in - the loops nested in them have their own breaks
+fn breaks(body: &Vec<Box<Node>>) -> bool{
+    return body.iter().any(|node| match &**node{
+        Node::Jump(val) => val.keyword == Keyword::Break,
+        Node::IfStatement(val)   => breaks(&val.body) || val.else_statement.as_ref().is_some_and(|node| breaks(&vec![node.clone()])),
+        Node::ElifStatement(val) => breaks(&val.body) || val.else_statement.as_ref().is_some_and(|node| breaks(&vec![node.clone()])),
+        Node::ElseStatement(val) => breaks(&val.body),
+        Node::Match(val) => val.arms.iter().any(|arm| breaks(&arm.body)),
+        _ => false,
+    });
+}
+
+// the index of the 'elif', 'else' or 'end' closing the body which starts at the given index,
+// skipping over the blocks nested in the body
+fn find_body_end(tokens: &Vec<Token>, start: usize) -> usize{
+    let mut openings = 0;
+    for i in start..tokens.len(){
+        if opens_block(tokens, i) {openings += 1;}
+        match tokens[i]{
+            Token::Keyword(Keyword::End) if openings == 0 => return i,
+            Token::Keyword(Keyword::End)    => openings -= 1,
+            Token::Keyword(Keyword::Elif) | Token::Keyword(Keyword::Else) if openings == 0 => return i,
+            _ => (),
+        }
+    }
+    return tokens.len() - 1;
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeIfStatement{
     condition: Box<Node>,
@@ -31,15 +105,19 @@ impl NodeIfStatement{
         let mut result: String = "if(".to_owned(); 
         result.push_str(&self.condition.to_c().to_owned());
         result.push_str("){\n");
-        for i in &self.body{
-            result.push_str(&(&i).to_c()[..]);
-            result.push_str(";\n");
-        }
+        result.push_str(&body_to_c(&self.body));
         result.push_str("}");
         if !self.else_statement.is_none() {result.push_str(&self.else_statement.as_ref().unwrap().to_c());}
         else {result.push_str("\n");}
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        analyze_condition(&mut self.condition, table)?;
+        analyze_body(&mut self.body, table)?;
+        if let Some(else_statement) = self.else_statement.as_mut() {else_statement.analyze(table)?;}
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeIfStatement{
@@ -61,13 +139,7 @@ impl From<&Vec<Token>> for NodeIfStatement{
         // here we basically find the index where this body closes and eventually an
         // elif/else statement starts, so we only split this body while the elif/else statement
         // remains whole
-        let else_statement_index = tokens
-            .iter()
-            .position(|token| *token == Token::Keyword(Keyword::Elif))
-            .unwrap_or(tokens
-                .iter()
-                .position(|token| *token == Token::Keyword(Keyword::Else))
-                .unwrap_or(tokens.len()));
+        let else_statement_index = find_body_end(tokens, i);
         let split_body_tokens = split_tokens(tokens[i .. else_statement_index].to_vec());
 
         for i in split_body_tokens{
@@ -103,15 +175,19 @@ impl NodeElifStatement{
         let mut result: String = "else if(".to_owned(); 
         result.push_str(&self.condition.to_c().to_owned());
         result.push_str("){\n");
-        for i in &self.body{
-            result.push_str(&(&i).to_c()[..]);
-            result.push_str(";\n");
-        }
+        result.push_str(&body_to_c(&self.body));
         result.push_str("}");
         if !self.else_statement.is_none() {result.push_str(&self.else_statement.as_ref().unwrap().to_c());}
         else {result.push_str("\n");}
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        analyze_condition(&mut self.condition, table)?;
+        analyze_body(&mut self.body, table)?;
+        if let Some(else_statement) = self.else_statement.as_mut() {else_statement.analyze(table)?;}
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeElifStatement{
@@ -131,13 +207,7 @@ impl From<&Vec<Token>> for NodeElifStatement{
         // here we basically find the index where this body closes and eventually an
         // elif/else statement starts, so we only split this body while the elif/else statement
         // remains whole
-        let else_statement_index = tokens
-            .iter()
-            .position(|token| *token == Token::Keyword(Keyword::Elif))
-            .unwrap_or(tokens
-                .iter()
-                .position(|token| *token == Token::Keyword(Keyword::Else))
-                .unwrap_or(tokens.len()));
+        let else_statement_index = find_body_end(tokens, i);
         let split_body_tokens = split_tokens(tokens[i .. else_statement_index].to_vec());
 
         for i in split_body_tokens{
@@ -167,22 +237,21 @@ impl NodeElseStatement{
 
     pub fn to_c(&self) -> String{
         let mut result: String = "else{".to_owned(); 
-        for i in &self.body{
-            result.push_str(&(&i).to_c()[..]);
-            result.push_str(";\n");
-        }
+        result.push_str(&body_to_c(&self.body));
         result.push_str("}\n");
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        analyze_body(&mut self.body, table)?;
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeElseStatement{
     fn from(tokens: &Vec<Token>) -> Self{
         let mut result = NodeElseStatement::new();
-        let end_position = tokens
-            .iter()
-            .position(|token| *token == Token::Keyword(Keyword::End))
-            .unwrap_or(tokens.len());
+        let end_position = find_body_end(tokens, 0);
         let split_body_tokens = split_tokens(tokens[0 .. end_position].to_vec());
         for i in split_body_tokens{
             result.body.push(Box::new(Node::from(i)));
@@ -209,13 +278,18 @@ impl NodeWhileLoop{
         let mut result: String = "while(".to_owned();
         result.push_str(&self.condition.to_c().to_owned());
         result.push_str("){\n");
-        for i in &self.body{
-            result.push_str(&(&i).to_c()[..]);
-            result.push_str(";\n");
-        }
+        result.push_str(&body_to_c(&self.body));
         result.push_str("}\n");
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        analyze_condition(&mut self.condition, table)?;
+        table.enter_loop();
+        analyze_body(&mut self.body, table)?;
+        table.exit_loop();
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeWhileLoop{
@@ -239,3 +313,389 @@ impl From<&Vec<Token>> for NodeWhileLoop{
         return result;
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct NodeForLoop{
+    variable: String,
+    iterable: Box<Node>,
+    body: Vec<Box<Node>>,
+    // set by the semantic pass
+    iterable_type: VarType,
+    var_type: VarType,
+}
+
+impl NodeForLoop{
+    fn new() -> Self{
+        NodeForLoop{
+            variable: String::new(),
+            iterable: Box::new(Node::None),
+            body: Vec::new(),
+            iterable_type: VarType::None,
+            var_type: VarType::None,
+        }
+    }
+
+    // the iterable is evaluated once, into a copy which the loop then walks over - the copy of a
+    // list is a handle to the same list, so the loop sees the elements the body adds to it
+    pub fn to_c(&self) -> String{
+        let items: String = format!("__items_{}", self.variable);
+        let index: String = format!("__index_{}", self.variable);
+        let length: String = match &self.iterable_type{
+            VarType::Array(_element, length) => length.to_string(),
+            list => format!("{}_len({})", list.to_c().trim(), items),
+        };
+        let access: &str = if matches!(self.iterable_type, VarType::List(_)) {"->"} else {"."};
+
+        let mut result: String = "{\n".to_owned();
+        result.push_str(&self.iterable_type.to_c());
+        result.push_str(&format!("{} = {};\n", items, self.iterable.to_c()));
+        result.push_str(&format!("for(size_t {} = 0; {} < {}; {}++){{\n", index, index, length, index));
+        result.push_str(&self.var_type.to_c());
+        result.push_str(&format!("{} = {}{}data[{}];\n", self.variable, items, access, index));
+        result.push_str(&body_to_c(&self.body));
+        result.push_str("}\n}\n");
+        return result;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.iterable_type = self.iterable.analyze(table)?;
+        self.var_type = match &self.iterable_type{
+            VarType::Array(element, _length) => *element.clone(),
+            VarType::List(element)           => *element.clone(),
+            var_type => return Err(format!("cannot iterate over a value of type {}", var_type)),
+        };
+        // the loop variable lives in a scope of its own, around the scope of the body
+        table.push_scope();
+        table.insert_variable(&self.variable, &self.var_type)?;
+        table.enter_loop();
+        analyze_body(&mut self.body, table)?;
+        table.exit_loop();
+        table.pop_scope();
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeForLoop{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut result = NodeForLoop::new();
+        let mut i = 0;
+
+        if tokens[i] == Token::Keyword(Keyword::For) {i += 1;}
+        result.variable = get_token_value!(&tokens[i], Token::Identifier).expect("expected the name of the loop variable").to_string();
+        i += 1;
+        if tokens[i] != Token::Keyword(Keyword::In) {panic!("expected 'in' after the loop variable");}
+        i += 1;
+
+        let mut buffer = Vec::<Token>::new();
+        while tokens[i] != Token::Colon{
+            buffer.push(tokens[i].clone());
+            i += 1;
+        }
+        result.iterable = Box::new(generate_expression(&buffer));
+        i += 1; // skipping over the ':'
+
+        let split_body_tokens = split_tokens(tokens[i .. tokens.len() - 1].to_vec());
+        for i in split_body_tokens{
+            result.body.push(Box::new(Node::from(i)));
+        }
+        return result;
+    }
+}
+
+// "break" and "continue" - the statements deferred in the body of the loop run first
+#[derive(Debug, Clone)]
+pub struct NodeJump{
+    keyword: Keyword,
+    cleanup: Vec<Node>, // set by the semantic pass
+}
+
+impl NodeJump{
+    pub fn new(keyword: Keyword) -> Self{
+        NodeJump {keyword: keyword, cleanup: Vec::new()}
+    }
+
+    pub fn to_c(&self) -> String{
+        let jump: &str = if self.keyword == Keyword::Break {"break"} else {"continue"};
+        if self.cleanup.is_empty() {return jump.to_string();}
+        let mut result: String = "{\n".to_owned();
+        result.push_str(&cleanup_to_c(&self.cleanup));
+        result.push_str(jump);
+        result.push_str(";\n}");
+        return result;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let jump: &str = if self.keyword == Keyword::Break {"break"} else {"continue"};
+        match table.loop_scope(){
+            Some(scope) => self.cleanup = table.deferred(scope),
+            None => return Err(format!("'{}' outside of a loop", jump)),
+        }
+        return Ok(VarType::None);
+    }
+}
+
+// "defer statement" - the statement runs when the block it is in is left, the last one deferred
+// first, so it is written out at the end of the block and before every return, break and continue
+// leaving it
+#[derive(Debug, Clone)]
+pub struct NodeDefer{
+    pub statement: Box<Node>,
+}
+
+impl NodeDefer{
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if table.return_type.is_none(){
+            return Err("'defer' outside of a function".to_string());
+        }
+        if matches!(*self.statement, Node::Return(_) | Node::Jump(_) | Node::Defer(_)){
+            return Err("a deferred statement cannot return, break, continue or defer".to_string());
+        }
+        self.statement.analyze(table)?;
+        table.defer(*self.statement.clone());
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeDefer{
+    fn from(tokens: &Vec<Token>) -> Self{
+        if tokens.len() < 2 {panic!("expected a statement after 'defer'");}
+        return NodeDefer {statement: Box::new(Node::from(tokens[1..].to_vec()))};
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern{
+    Wildcard,                                         // case _:
+    Variant{name: String, path: String, bindings: Vec<String>}, // case Circle(radius): - the path is the name as written
+    Value(Box<Node>),                                 // case 1:
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArm{
+    pattern: Pattern,
+    body: Vec<Box<Node>>,
+    // set by the semantic pass for the variants
+    variant_fields: StructFields,
+}
+
+// match value:
+//     case Pattern:
+//         ...
+// end
+#[derive(Debug, Clone)]
+pub struct NodeMatch{
+    value: Box<Node>,
+    arms: Vec<MatchArm>,
+    // set by the semantic pass
+    value_type: VarType,
+    plain_enum: bool,
+}
+
+impl NodeMatch{
+    fn new() -> Self{
+        NodeMatch{
+            value: Box::new(Node::None),
+            arms: Vec::new(),
+            value_type: VarType::None,
+            plain_enum: false,
+        }
+    }
+
+    fn condition_to_c(&self, pattern: &Pattern) -> String{
+        match pattern{
+            // the options and the results are tagged with a bool
+            Pattern::Variant {name, ..} if matches!(self.value_type, VarType::Option(_)) => {
+                return format!("{}__match.is_some", if name == "some" {""} else {"!"});
+            },
+            Pattern::Variant {name, ..} if matches!(self.value_type, VarType::Result(_, _)) => {
+                return format!("{}__match.is_ok", if name == "ok" {""} else {"!"});
+            },
+            Pattern::Variant {name, ..} => {
+                let tag: String = format!("{}__{}", self.value_type.to_c().trim(), name);
+                if self.plain_enum {return format!("__match == {}", tag);}
+                return format!("__match.tag == {}", tag);
+            },
+            Pattern::Value(value) => return format!("__match == ({})", value.to_c()),
+            Pattern::Wildcard => return "1".to_string(),
+        }
+    }
+
+    // the value is evaluated once, into "__match", and the arms are an if/else chain
+    // (a switch would take over the 'break' of an enclosing loop)
+    pub fn to_c(&self) -> String{
+        let mut result: String = "{\n".to_owned();
+        result.push_str(&self.value_type.to_c());
+        result.push_str("__match = ");
+        result.push_str(&self.value.to_c());
+        result.push_str(";\n");
+        for (i, arm) in self.arms.iter().enumerate(){
+            if i != 0 {result.push_str("else ");}
+            match &arm.pattern{
+                Pattern::Wildcard if i != 0 => result.push_str("{\n"),
+                pattern => result.push_str(&format!("if({}){{\n", self.condition_to_c(pattern))),
+            }
+            if let Pattern::Variant {name, bindings, ..} = &arm.pattern{
+                for (binding, (field, field_type)) in bindings.iter().zip(arm.variant_fields.iter()){
+                    if binding == "_" {continue;}
+                    match self.value_type{
+                        VarType::Enum(_) => result.push_str(&format!("{}{} = __match.as.{}.{};\n", field_type.to_c(), binding, name, field)),
+                        _ => result.push_str(&format!("{}{} = __match.{};\n", field_type.to_c(), binding, field)),
+                    }
+                }
+            }
+            result.push_str(&body_to_c(&arm.body));
+            result.push_str("}\n");
+        }
+        result.push_str("}\n");
+        return result;
+    }
+
+    // every variant of an enum has to be handled, the other values need a '_' arm
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.value_type = self.value.analyze(table)?;
+        let variants: EnumVariants = match &self.value_type{
+            VarType::Enum(name) => table.get_enum(name).unwrap().clone(),
+            VarType::Option(_) | VarType::Result(_, _) => sum_variants(&self.value_type),
+            var_type if is_numeric(var_type) && !is_float(var_type) => Vec::new(),
+            var_type => return Err(format!("cannot match on a value of type {}", var_type)),
+        };
+        self.plain_enum = table.is_plain_enum(&self.value_type);
+        let is_enum: bool = matches!(self.value_type, VarType::Enum(_) | VarType::Option(_) | VarType::Result(_, _));
+
+        let mut handled: Vec<String> = Vec::new();
+        let mut exhaustive: bool = false;
+        for arm in self.arms.iter_mut(){
+            if exhaustive {return Err("this case is never reached, it comes after '_'".to_string());}
+            table.push_scope();
+            // the names are constants when the value is not an enum - "case LIMIT:"
+            if let Pattern::Variant {path, bindings, ..} = &arm.pattern{
+                if !is_enum && bindings.is_empty(){
+                    arm.pattern = Pattern::Value(Box::new(Node::VariableCall(NodeVariableCall::new(path.to_string()))));
+                }
+            }
+            match &mut arm.pattern{
+                Pattern::Wildcard => exhaustive = true,
+                Pattern::Variant {name, bindings, ..} if is_enum => {
+                    let fields = match variants.iter().find(|(variant, _fields)| variant == name){
+                        Some((_variant, fields)) => fields.clone(),
+                        None if matches!(self.value_type, VarType::Enum(_)) => return Err(format!("enum '{}' has no variant '{}'", self.value_type, name)),
+                        None => return Err(format!("'{}' is not a case of {}", name, self.value_type)),
+                    };
+                    if handled.contains(name) {return Err(format!("variant '{}' is matched more than once", name));}
+                    handled.push(name.to_string());
+                    if !bindings.is_empty() && bindings.len() != fields.len(){
+                        return Err(format!("variant '{}' has {} value(s) but {} names were given", name, fields.len(), bindings.len()));
+                    }
+                    for (binding, (_field, field_type)) in bindings.iter().zip(fields.iter()){
+                        if binding != "_" {table.insert_variable(binding, field_type)?;}
+                    }
+                    arm.variant_fields = fields;
+                },
+                Pattern::Variant {name, ..} => return Err(format!("'{}' is not a value of type {}", name, self.value_type)),
+                Pattern::Value(value) => {
+                    let value_type = value.analyze(table)?;
+                    if is_enum || !is_numeric(&value_type) || is_float(&value_type){
+                        return Err(format!("cannot match a value of type {} against a value of type {}", self.value_type, value_type));
+                    }
+                },
+            }
+            analyze_body(&mut arm.body, table)?;
+            table.pop_scope();
+        }
+
+        if !exhaustive{
+            if !is_enum {return Err(format!("a match on a value of type {} needs a '_' case", self.value_type));}
+            let missing: Vec<String> = variants
+                .iter()
+                .map(|(variant, _fields)| variant.to_string())
+                .filter(|variant| !handled.contains(variant))
+                .collect();
+            if !missing.is_empty(){
+                return Err(format!("the match on {} does not handle {}", self.value_type, missing.join(", ")));
+            }
+        }
+        return Ok(VarType::None);
+    }
+}
+
+// "_", "Variant", "Enum.Variant", "Variant(a, b)", "some(x)", "none" or a value
+fn parse_pattern(tokens: &Vec<Token>) -> Pattern{
+    match tokens.as_slice(){
+        [Token::Identifier(name)] if name == "_" => return Pattern::Wildcard,
+        // the empty option
+        [Token::Keyword(Keyword::None)] => return Pattern::Variant {name: "none".to_string(), path: "none".to_string(), bindings: Vec::new()},
+        _ => (),
+    }
+    let mut i = 0;
+    let mut name: String = String::new();
+    let mut path: Vec<String> = Vec::new();
+    while let Some(Token::Identifier(val)) = tokens.get(i){
+        name = val.to_string(); // only the name of the variant matters, the enum is known
+        path.push(val.to_string());
+        if tokens.get(i + 1) != Some(&Token::Dot) {break;}
+        i += 2;
+    }
+    if name.is_empty() || !(i + 1 == tokens.len() || tokens[i + 1] == Token::LPar){
+        // integers - the identifiers are treated as variants until the type is known
+        return Pattern::Value(Box::new(generate_expression(tokens)));
+    }
+
+    let mut bindings: Vec<String> = Vec::new();
+    for token in &tokens[(i + 1).min(tokens.len())..]{
+        match token{
+            Token::Identifier(binding) => bindings.push(binding.to_string()),
+            Token::LPar | Token::RPar | Token::Comma => (),
+            token => panic!("unexpected {:?} in the pattern of variant '{}'", token, name),
+        }
+    }
+    return Pattern::Variant {name: name, path: path.join("."), bindings: bindings};
+}
+
+impl From<&Vec<Token>> for NodeMatch{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut result = NodeMatch::new();
+        let mut i = 0;
+
+        if tokens[i] == Token::Keyword(Keyword::Match) {i += 1;}
+        let mut buffer = Vec::<Token>::new();
+        while tokens[i] != Token::Colon{
+            buffer.push(tokens[i].clone());
+            i += 1;
+        }
+        result.value = Box::new(generate_expression(&buffer));
+        i += 1; // skipping over the ':'
+
+        // the arms start at the 'case' keywords which are not nested in another block
+        let mut openings: i32 = 0;
+        let mut arm_tokens: Vec<Vec<Token>> = Vec::new();
+        for (j, token) in tokens[.. tokens.len() - 1].iter().enumerate().skip(i){
+            if opens_block(tokens, j) {openings += 1;}
+            match token{
+                Token::Keyword(Keyword::End) => openings -= 1,
+                Token::Keyword(Keyword::Case) if openings == 0 => {
+                    arm_tokens.push(Vec::new());
+                    continue;
+                },
+                _ => (),
+            }
+            match arm_tokens.last_mut(){
+                Some(arm) => arm.push(token.clone()),
+                None if *token == Token::NewLine => (),
+                None => panic!("expected 'case' in the match block"),
+            }
+        }
+
+        for arm in arm_tokens{
+            let colon = arm.iter().position(|token| *token == Token::Colon).expect("expected ':' after the case pattern");
+            let mut body_tokens: Vec<Token> = arm[colon + 1 ..].to_vec();
+            // split_tokens() expects the body to start on a new line
+            if body_tokens.first() != Some(&Token::NewLine) {body_tokens.insert(0, Token::NewLine);}
+            result.arms.push(MatchArm{
+                pattern: parse_pattern(&arm[..colon].to_vec()),
+                body: split_tokens(body_tokens).into_iter().map(|tokens| Box::new(Node::from(tokens))).collect(),
+                variant_fields: Vec::new(),
+            });
+        }
+        return result;
+    }
+}
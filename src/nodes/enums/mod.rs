@@ -0,0 +1,108 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+use super::structs::resolve_fields;
+
+// enum Name:
+//     Variant
+//     Variant(field: type, ...)
+// end
+#[derive(Debug, Clone)]
+pub struct NodeEnumDefinition{
+    name: String,
+    full_name: String, // set by the semantic pass
+    variants: EnumVariants,
+}
+
+impl NodeEnumDefinition{
+    // the C type is declared with the other types, before any of the code
+    pub fn to_c(&self) -> String{
+        "".to_string()
+    }
+
+    pub fn declare(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        self.full_name = table.declare_enum(&self.name)?;
+        return Ok(());
+    }
+
+    pub fn define(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        if self.variants.is_empty(){
+            return Err(format!("enum '{}' has no variants", self.name));
+        }
+        let mut variants: EnumVariants = Vec::new();
+        for (variant, fields) in &self.variants{
+            if variants.iter().any(|(name, _fields)| name == variant){
+                return Err(format!("enum '{}' has more than one variant '{}'", self.name, variant));
+            }
+            let fields = resolve_fields(table, &format!("variant '{}.{}'", self.name, variant), fields)?;
+            variants.push((variant.to_string(), fields));
+        }
+        table.define_enum(&self.full_name, variants);
+        return Ok(());
+    }
+
+    // everything is done before the rest of the module is analyzed
+    pub fn analyze(&mut self, _table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.full_name.is_empty(){
+            return Err("enums can only be defined at the top level of a module".to_string());
+        }
+        return Ok(VarType::None);
+    }
+}
+
+// the value of a variant of a plain enum is just its tag - "Color__Red", the other enums are
+// compound literals - "((Shape){.tag = Shape__Empty})" (the variants with values are
+// made by the function call syntax - "Shape.Circle(2)")
+pub fn variant_to_c(table: &SymbolTable, enum_type: &VarType, variant: usize) -> Result<String, String>{
+    let c_name: String = enum_type.to_c().trim().to_string();
+    let (name, fields) = match enum_type{
+        VarType::Enum(full_name) => &table.get_enum(full_name).unwrap()[variant],
+        _ => return Ok(String::new()),
+    };
+    if !fields.is_empty(){
+        return Err(format!("variant '{}.{}' takes {} value(s)", enum_type, name, fields.len()));
+    }
+    if table.is_plain_enum(enum_type) {return Ok(format!("{}__{}", c_name, name));}
+    return Ok(format!("(({}){{.tag = {}__{}}})", c_name, c_name, name));
+}
+
+// the values of a variant may be named - "Circle(radius: f64)" - or not - "Circle(f64)",
+// in which case they are named by their position ("_0", "_1", ...)
+fn parse_variant_fields(tokens: &Vec<Token>, i: &mut usize) -> StructFields{
+    let mut result: StructFields = Vec::new();
+    *i += 1; // skipping over the '('
+    while tokens[*i] != Token::RPar{
+        let name: String = match (&tokens[*i], &tokens[*i + 1]){
+            (Token::Identifier(name), Token::Colon) => {
+                *i += 2;
+                name.to_string()
+            },
+            _ => format!("_{}", result.len()),
+        };
+        result.push((name, parse_type(tokens, i)));
+        if tokens[*i] == Token::Comma {*i += 1;}
+    }
+    *i += 1;
+    return result;
+}
+
+impl From<&Vec<Token>> for NodeEnumDefinition{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut result = NodeEnumDefinition {name: String::new(), full_name: String::new(), variants: Vec::new()};
+        let mut i = 1;
+        result.name = get_token_value!(&tokens[i], Token::Identifier).expect("expected the name of the enum").to_string();
+        i += 1;
+        if tokens[i] != Token::Colon {panic!("expected ':' after the name of enum '{}'", result.name);}
+        i += 1;
+
+        while i < tokens.len() && tokens[i] != Token::Keyword(Keyword::End){
+            if tokens[i] == Token::NewLine {i += 1; continue;}
+            let variant = get_token_value!(&tokens[i], Token::Identifier).expect("expected the name of a variant").to_string();
+            i += 1;
+            let mut fields: StructFields = Vec::new();
+            if tokens[i] == Token::LPar {fields = parse_variant_fields(tokens, &mut i);}
+            result.variants.push((variant, fields));
+        }
+        return result;
+    }
+}
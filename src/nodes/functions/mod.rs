@@ -1,103 +1,505 @@
 use crate::lexer::*;
 use crate::parser::*;
+use crate::semantic::*;
+use crate::builtins::{self, Lowering};
+use crate::runtime;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use super::Node;
-use crate::nodes::*;
+use super::variables::NodeVariableCall;
+use super::binary_expression::generate_expression;
+use super::{get_closing, split_arguments, body_to_c, cleanup_to_c};
+use super::control_flow::always_returns;
+
+// the type the C side of an 'extern fn' uses, as a C programmer would write it - "int" for i32, which
+// chal keeps in a "long" - the values are converted by the calls
+fn extern_type(var_type: &VarType, argument: bool) -> String{
+    match var_type{
+        VarType::I8  => return "signed char ".to_string(),
+        VarType::I16 => return "short ".to_string(),
+        VarType::I32 => return "int ".to_string(),
+        VarType::U16 => return "unsigned short ".to_string(),
+        VarType::U32 => return "unsigned int ".to_string(),
+        VarType::Str if argument => return "const char* ".to_string(),
+        _ => return var_type.to_c(),
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct NodeFunctionDefinition{
     name: String,
+    c_name: String, // set by the semantic pass
+    type_params: Vec<(String, Option<String>)>, // "[T, U: numeric]", the generic functions are only generated for their calls
     arg_names: Vec<String>,
     arg_types: Vec<VarType>,
+    defaults: Vec<Option<Box<Node>>>, // "name: type = value"
     return_type: VarType,
     body: Vec<Box<Node>>,
+    external: bool, // "extern fn" - only the signature, the C side defines it
+    exported: bool, // "export fn" - called from C, its prototype goes to the generated header
+    declared: bool, // an 'extern fn' of a module including C headers, or one of the prelude's, is declared by them
 }
 
 impl NodeFunctionDefinition{
-    pub fn to_c(&self) -> String{
+    fn signature_to_c(&self) -> String{
         let mut result: String = "".to_string().to_owned();
-        result.push_str(&self.return_type.to_c()[..]);
-        result.push_str(&self.name[..]);
+        // only the exported functions are visible outside of a library, the C ones are defined elsewhere
+        if !self.exported && !self.external {result.push_str("__CHAL_LOCAL ");}
+        let to_c = |var_type: &VarType, argument: bool| if self.external {extern_type(var_type, argument)} else {var_type.to_c()};
+        result.push_str(&to_c(&self.return_type, false));
+        result.push_str(&self.c_name[..]);
         result.push_str("(");
 
         for i in 0..self.arg_names.len(){
-            result.push_str(&self.arg_types[i].to_c()[..]);
+            result.push_str(&to_c(&self.arg_types[i], true));
             result.push_str(&self.arg_names[i][..]);
             if i != self.arg_names.len() - 1 {result.push_str(", ");}
         }
-        result.push_str("){\n");
+        result.push_str(")");
+        return result;
+    }
 
-        for i in &self.body{
-            result.push_str(&(&i).to_c()[..]);
-            result.push_str(";\n");
+    // every function is declared before any of them is defined, so they can call each other - the
+    // C functions of a module without headers are declared from their signatures, with the C types
+    pub fn prototype(&self) -> String{
+        if !self.type_params.is_empty() || self.declared {return String::new();}
+        let mut result: String = self.signature_to_c();
+        result.push_str(";\n");
+        return result;
+    }
+
+    pub fn is_exported(&self) -> bool{
+        return self.exported;
+    }
+
+    // the C entry point calls the 'main' of the program with the command-line arguments
+    // and returns its exit code
+    fn entry_point_to_c(&self) -> String{
+        let mut result: String = "int main(int argc, char** argv){\n".to_string();
+        let mut call: String = format!("{}()", self.c_name);
+        if let Some(args_type) = self.arg_types.first(){
+            result.push_str(&format!("{}args = {}_from(argv, argc);\n", args_type.to_c(), args_type.to_c().trim()));
+            call = format!("{}(args)", self.c_name);
+        }
+        if self.return_type == VarType::None{
+            result.push_str(&format!("{};\nreturn 0;\n}}\n", call));
+        }else{
+            result.push_str(&format!("return {};\n}}\n", call));
         }
+        return result;
+    }
+
+    pub fn to_c(&self) -> String{
+        if !self.type_params.is_empty() || self.external {return String::new();}
+        let mut result: String = self.signature_to_c();
+        result.push_str("{\n");
+
+        result.push_str(&body_to_c(&self.body));
         result.push_str("}\n");
+        if self.c_name == "__chal_main" {result.push_str(&self.entry_point_to_c());}
         return result;
     }
+
+    // the types of a generic function are resolved with its type parameters left in them, every call
+    // infers what they stand for - its body is only analyzed for the types it is called with
+    fn declare_generic(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        let template = Node::FunctionDefinition(self.clone());
+        for (i, (name, constraint)) in self.type_params.iter().enumerate(){
+            if self.type_params[..i].iter().any(|(previous, _constraint)| previous == name){
+                return Err(format!("the type parameter '{}' of '{}' is given more than once", name, self.name));
+            }
+            if let Some(constraint) = constraint{
+                if !CONSTRAINTS.contains(&constraint.as_str()){
+                    return Err(format!("unknown constraint '{}' of type parameter '{}', expected one of: {}", constraint, name, CONSTRAINTS.join(", ")));
+                }
+            }
+        }
+        if self.defaults.iter().any(|default| default.is_some()){
+            return Err(format!("the arguments of the generic function '{}' cannot have default values", self.name));
+        }
+
+        table.set_type_params(self.type_params.iter().map(|(name, _constraint)| (name.to_string(), VarType::Generic(name.to_string()))).collect());
+        let resolved = self.resolve_signature(table);
+        table.set_type_params(Vec::new());
+        resolved?;
+        for (name, _constraint) in &self.type_params{
+            if !self.arg_types.iter().any(|arg_type| contains_type_param(arg_type, name)){
+                return Err(format!("the type parameter '{}' of '{}' is not used by its arguments, so it cannot be inferred", name, self.name));
+            }
+        }
+
+        let signature = FunctionSignature {
+            arg_names: self.arg_names.clone(),
+            arg_types: self.arg_types.clone(),
+            defaults: vec![None; self.arg_names.len()],
+            return_type: self.return_type.clone(),
+            c_name: String::new(),
+            unmangled: false,
+        };
+        table.insert_generic(&self.name, GenericFunction {type_params: self.type_params.clone(), signature: signature, definition: template})?;
+        self.c_name = self.name.to_string();
+        return Ok(());
+    }
+
+    fn resolve_signature(&mut self, table: &SymbolTable) -> Result<(), String>{
+        for i in 0..self.arg_types.len(){
+            self.arg_types[i] = table.resolve_type(&self.arg_types[i])?;
+        }
+        self.return_type = table.resolve_type(&self.return_type)?;
+        return Ok(());
+    }
+
+    // the copy of a generic function made for the types its type parameters are bound to in the table
+    pub fn instantiate(&mut self, table: &mut SymbolTable, c_name: &String) -> Result<(), String>{
+        self.type_params.clear();
+        self.resolve_signature(table)?;
+        self.c_name = c_name.to_string();
+        self.analyze(table)?;
+        return Ok(());
+    }
+
+    // the signatures of all the functions of a module are known before any of the bodies is analyzed
+    pub fn declare(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        if self.external && !self.type_params.is_empty(){
+            return Err(format!("the C function '{}' cannot have type parameters", self.name));
+        }
+        if self.exported && !self.type_params.is_empty(){
+            return Err(format!("the generic function '{}' cannot be exported, C has no type parameters", self.name));
+        }
+        if !self.type_params.is_empty() {return self.declare_generic(table);}
+        self.resolve_signature(table)?;
+        self.declared = self.external && (table.module_includes() || runtime::prelude_declares(&self.name));
+        if self.exported{
+            if self.name == "main" {return Err("'main' cannot be exported".to_string());}
+            for i in 0..self.arg_names.len(){
+                table.export_type(&self.arg_types[i])
+                    .map_err(|error| format!("the exported function '{}' cannot take '{}': {}", self.name, self.arg_names[i], error))?;
+            }
+            table.export_type(&self.return_type)
+                .map_err(|error| format!("the exported function '{}' cannot return {}: {}", self.name, self.return_type, error))?;
+        }
+        if self.name == "main" && table.in_main_module(){
+            let arguments = self.arg_types.is_empty() || self.arg_types == vec![VarType::List(Box::new(VarType::Str))];
+            if !arguments || !(self.return_type == VarType::None || self.return_type == VarType::I32){
+                return Err("'main' must take no arguments or a single list[str], and return none or i32".to_string());
+            }
+        }
+        let signature = FunctionSignature {
+            arg_names: self.arg_names.clone(),
+            arg_types: self.arg_types.clone(),
+            defaults: Vec::new(),
+            return_type: self.return_type.clone(),
+            c_name: String::new(),
+            unmangled: self.external || self.exported,
+        };
+        self.c_name = table.insert_function(&self.name, signature)?.c_name;
+        return Ok(());
+    }
+
+    // the default values can use the constants, so they are only analyzed once the globals are known -
+    // they are copied to the calls, so they have to be known at compile time
+    pub fn define(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        if !self.type_params.is_empty() {return Ok(());}
+        let mut defaults: Vec<Option<Node>> = Vec::new();
+        for i in 0..self.arg_names.len(){
+            let default = match &mut self.defaults[i]{
+                Some(default) => default,
+                None if defaults.iter().any(|default| default.is_some()) => {
+                    return Err(format!("the argument '{}' of '{}' needs a default value, as the ones before it have one", self.arg_names[i], self.name));
+                },
+                None => {
+                    defaults.push(None);
+                    continue;
+                },
+            };
+            let mut default_type = default.analyze(table)?;
+            default_type = default.coerce(default_type, &self.arg_types[i]);
+            if !is_compatible(&default_type, &self.arg_types[i]){
+                return Err(format!("the default value of '{}' must be of type {}, found {}", self.arg_names[i], self.arg_types[i], default_type));
+            }
+            if default.fold().is_none(){
+                return Err(format!("the default value of '{}' must be known at compile time", self.arg_names[i]));
+            }
+            defaults.push(Some((**default).clone()));
+        }
+        self.c_name = table.define_function(&self.name, &self.arg_types, defaults);
+        return Ok(());
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.c_name.is_empty(){
+            return Err("functions can only be defined at the top level of a module".to_string());
+        }
+        if !self.type_params.is_empty() {return Ok(VarType::None);}
+        table.push_scope();
+        table.return_type = Some(self.return_type.clone());
+        for i in 0..self.arg_names.len(){
+            table.insert_variable(&self.arg_names[i], &self.arg_types[i])?;
+        }
+        for node in self.body.iter_mut(){
+            node.analyze(table)?;
+        }
+        table.return_type = None;
+        table.pop_scope();
+        if self.return_type != VarType::None && !self.external && !always_returns(&self.body){
+            return Err(format!("'{}' does not return a value of type {} on every path", self.name, self.return_type));
+        }
+        return Ok(VarType::None);
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct NodeFunctionCall{
     name: String,
+    c_name: String, // set by the semantic pass
     args: Vec<Box<Node>>,
+    arg_names: Vec<Option<String>>, // "name: value", the names of the positional arguments are None
+    lowering: Option<Lowering>, // set by the semantic pass for the built-in functions
+    // set by the semantic pass when this is a struct literal or an enum variant with values
+    literal_type: Option<VarType>,
+    variant: Option<String>,
+    // the value holding the called function, when it is not called by its name - "handlers[0](x)" -
+    // the semantic pass sets it for the variables too, along with the type of the function
+    callee: Option<Box<Node>>,
+    callee_type: VarType,
 }
 
-pub fn get_call(token: &Token) -> Node{
-    match token{
-        Token::Int8(val)    => return Node::ValueInt(NodeValueInt::new(*val as i64, VarType::I8)),
-        Token::Int16(val)   => return Node::ValueInt(NodeValueInt::new(*val as i64, VarType::I16)),
-        Token::Int32(val)   => return Node::ValueInt(NodeValueInt::new(*val as i64, VarType::I32)),
-        Token::Int64(val)   => return Node::ValueInt(NodeValueInt::new(*val as i64, VarType::I64)),
-        Token::UInt8(val)   => return Node::ValueUInt(NodeValueUInt::new(*val as u64, VarType::U8)),
-        Token::UInt16(val)  => return Node::ValueUInt(NodeValueUInt::new(*val as u64, VarType::U16)),
-        Token::UInt32(val)  => return Node::ValueUInt(NodeValueUInt::new(*val as u64, VarType::U32)),
-        Token::UInt64(val)  => return Node::ValueUInt(NodeValueUInt::new(*val as u64, VarType::U64)),
-        Token::Float32(val) => return Node::ValueFloat(NodeValueFloat::new(*val as f64, VarType::F32)),
-        Token::Float64(val) => return Node::ValueFloat(NodeValueFloat::new(*val as f64, VarType::F64)),
-        Token::String(val)  => return Node::ValueString(NodeValueString::new(val.to_string(), VarType::Str)),
-        _ => return Node::VariableCall(NodeVariableCall::from(token)),
+impl NodeFunctionCall{
+    pub fn new(name: String, args: Vec<Box<Node>>, arg_names: Vec<Option<String>>) -> Self{
+        NodeFunctionCall {name: name, c_name: String::new(), args: args, arg_names: arg_names, lowering: None, literal_type: None, variant: None, callee: None, callee_type: VarType::None}
     }
-}
 
-pub fn format_printf(string: String) -> Vec<Box<Node>>{
-    let formatting = regex::Regex::new(r"(?:[^\\])(\{\w+[^\\\}]\})").unwrap();    
-    let mut result = string.to_owned();
-    let mut vec_result = Vec::<Box<Node>>::new();
+    pub fn indirect(callee: Node, args: Vec<Box<Node>>) -> Self{
+        let arg_names: Vec<Option<String>> = vec![None; args.len()];
+        let mut result = NodeFunctionCall::new(String::new(), args, arg_names);
+        result.callee = Some(Box::new(callee));
+        return result;
+    }
+
+    // a compound literal naming every field - "((Point){.x = 1, .y = 2})", the values of
+    // an enum variant are a struct in the union of the enum -
+    // "((Shape){.tag = Shape__Circle, .as.Circle = {.radius = 2}})"
+    fn literal_to_c(&self, var_type: &VarType) -> String{
+        let mut fields: String = String::new();
+        for i in 0..self.args.len(){
+            fields.push('.');
+            fields.push_str(self.arg_names[i].as_ref().unwrap());
+            fields.push_str(" = ");
+            fields.push_str(&self.args[i].to_c());
+            if i != self.args.len() - 1 {fields.push_str(", ");}
+        }
 
-    for matches in formatting.captures_iter(&string){
-        let var_name: String = (&matches[0][2..matches[0].len()-1]).to_string();
-        vec_result.push(Box::new(Node::VariableCall(NodeVariableCall::from(&Token::Identifier(var_name.to_owned())))));
-        result = str::replace(&result, &matches[0][1..], &VarType::to_c_printf(&variables_get(&var_name)));
+        let c_name: String = var_type.to_c().trim().to_string();
+        match &self.variant{
+            Some(variant) => return format!("(({}){{.tag = {}__{}, .as.{} = {{{}}}}})", c_name, c_name, variant, variant, fields),
+            None => return format!("(({}){{{}}})", c_name, fields),
+        }
     }
 
-    vec_result.insert(0, Box::new(Node::ValueString(NodeValueString::new(result, VarType::Str))));
-    return vec_result;
-}
+    // the arguments may be given in any order by their names, but every field needs a value
+    fn analyze_literal(&mut self, owner: String, fields: &StructFields, mut arg_types: Vec<VarType>) -> Result<(), String>{
+        if self.args.len() != fields.len(){
+            return Err(format!("{} has {} field(s) but {} were given", owner, fields.len(), self.args.len()));
+        }
+        let first_named: usize = self.arg_names.iter().position(|name| name.is_some()).unwrap_or(self.args.len());
+        for i in 0..self.args.len(){
+            let field: String = match &self.arg_names[i]{
+                Some(name) => name.to_string(),
+                None if i > first_named => {
+                    return Err(format!("a positional field cannot follow a named one in the literal of {}", owner));
+                },
+                None => fields[i].0.to_string(),
+            };
+            if self.arg_names[..i].iter().any(|name| name.as_ref() == Some(&field)){
+                return Err(format!("the field '{}' of {} is given more than once", field, owner));
+            }
+            let field_type = match fields.iter().find(|(name, _field_type)| *name == field){
+                Some((_name, field_type)) => field_type,
+                None => return Err(format!("{} has no field '{}'", owner, field)),
+            };
+            arg_types[i] = self.args[i].coerce(arg_types[i].clone(), field_type);
+            if !is_compatible(&arg_types[i], field_type){
+                return Err(format!("the field '{}' of {} must be of type {}, found {}", field, owner, field_type, arg_types[i]));
+            }
+            self.arg_names[i] = Some(field);
+        }
+        return Ok(());
+    }
 
-impl NodeFunctionCall{
-    pub fn new(tokens: &Vec<Token>) -> Self{
-        let mut result: NodeFunctionCall = NodeFunctionCall {name: get_token_value!(&tokens[0], Token::Identifier).unwrap().to_string(), args: Vec::new()};
-        for i in 2..(tokens.len() - 1){
-            result.args.push(Box::new(get_call(&tokens[i])));
+    fn analyze_struct_literal(&mut self, table: &mut SymbolTable, struct_type: VarType, arg_types: Vec<VarType>) -> Result<VarType, String>{
+        let fields: StructFields = match &struct_type{
+            VarType::Struct(name) => table.get_struct(name).unwrap().clone(),
+            _ => return Err(format!("enum '{}' cannot be called, only its variants can", struct_type)),
+        };
+        self.analyze_literal(format!("struct '{}'", struct_type), &fields, arg_types)?;
+        self.literal_type = Some(struct_type.clone());
+        return Ok(struct_type);
+    }
+
+    // "Shape.Circle(2)" - the variants without values are not called
+    fn analyze_variant_literal(&mut self, table: &mut SymbolTable, enum_type: VarType, variant: usize, arg_types: Vec<VarType>) -> Result<VarType, String>{
+        let (name, fields) = match &enum_type{
+            VarType::Enum(full_name) => table.get_enum(full_name).unwrap()[variant].clone(),
+            _ => return Ok(VarType::None),
+        };
+        if fields.is_empty(){
+            return Err(format!("variant '{}.{}' takes no values, so it is written without the parentheses", enum_type, name));
         }
-        // if tokens[tokens.len() - 1] != '(' {panic!()} - todo!
-        return result;
+        self.analyze_literal(format!("variant '{}.{}'", enum_type, name), &fields, arg_types)?;
+        self.literal_type = Some(enum_type.clone());
+        self.variant = Some(name);
+        return Ok(enum_type);
     }
 
-    pub fn to_c(&self) -> String{
-        let mut result: String = self.name.to_owned();
-        if self.name == "print" {
-            result.push_str("f(");
-            let new_args: Vec<Box<Node>> = format_printf(self.args[0].to_c());
-            for i in 0..new_args.len(){
-                result.push_str(&new_args[i].to_c().to_owned());
-                if i != new_args.len() - 1 {result.push_str(", ");}
+    // a call of a closure
+    fn analyze_indirect(&mut self, callee_type: VarType, mut arg_types: Vec<VarType>) -> Result<VarType, String>{
+        let (callee_args, return_type) = match &callee_type{
+            VarType::Function(callee_args, return_type) => (callee_args.clone(), *return_type.clone()),
+            _ => return Err(format!("a value of type {} cannot be called", callee_type)),
+        };
+        let name: String = if self.name.is_empty() {callee_type.to_string()} else {self.name.to_string()};
+        for i in 0..arg_types.len().min(callee_args.len()){
+            arg_types[i] = self.args[i].coerce(arg_types[i].clone(), &callee_args[i]);
+        }
+        check_arguments(&name, &callee_args, &arg_types)?;
+        self.callee_type = callee_type;
+        return Ok(return_type);
+    }
+
+    // the type parameters are inferred from the arguments, the values of the same type parameter
+    // have to be of the same type (or all numbers)
+    fn analyze_generic(&mut self, table: &mut SymbolTable, generic: GenericFunction, arg_types: Vec<VarType>) -> Result<VarType, String>{
+        let mut arg_types = self.order_arguments(&generic.signature, arg_types)?;
+        let mut bindings: Vec<(String, VarType)> = Vec::new();
+        for i in 0..arg_types.len(){
+            infer_type_params(&generic.signature.arg_types[i], &arg_types[i], &mut bindings)?;
+        }
+        let mut types: Vec<VarType> = Vec::new();
+        for (name, constraint) in &generic.type_params{
+            let var_type = match bindings.iter().find(|(type_param, _var_type)| type_param == name){
+                Some((_name, var_type)) => var_type.clone(),
+                None => return Err(format!("cannot infer the type parameter '{}' of '{}' from its arguments", name, self.name)),
+            };
+            if let Some(constraint) = constraint{
+                if !table.satisfies(&var_type, constraint){
+                    return Err(format!("'{}' needs {} to be {}, found {}", self.name, name, constraint, var_type));
+                }
             }
+            types.push(var_type);
+        }
 
-            result.push_str(")");
-            return result;
+        let expected: Vec<VarType> = generic.signature.arg_types.iter().map(|arg_type| substitute(arg_type, &bindings)).collect();
+        for i in 0..arg_types.len(){
+            arg_types[i] = self.args[i].coerce(arg_types[i].clone(), &expected[i]);
+        }
+        check_arguments(&self.name, &expected, &arg_types)?;
+        self.c_name = table.instantiate(&self.name, types)?;
+        return Ok(substitute(&generic.signature.return_type, &bindings));
+    }
+
+    // finds the given argument for every argument of the function, None meaning its default value
+    fn arrange_arguments(&self, signature: &FunctionSignature) -> Result<Vec<Option<usize>>, String>{
+        let count: usize = signature.arg_types.len();
+        let given: usize = self.args.len();
+        let first_named: usize = self.arg_names.iter().position(|name| name.is_some()).unwrap_or(given);
+        let mut result: Vec<Option<usize>> = vec![None; count];
+        for i in 0..given{
+            let position: usize = match &self.arg_names[i]{
+                Some(name) => match signature.arg_names.iter().position(|arg_name| arg_name == name){
+                    Some(position) => position,
+                    None => return Err(format!("'{}' has no argument named '{}'", self.name, name)),
+                },
+                None if i > first_named => {
+                    return Err(format!("a positional argument cannot follow a named one in the call of '{}'", self.name));
+                },
+                None if i >= count => {
+                    return Err(format!("'{}' takes {} argument(s) but {} were given", self.name, count, given));
+                },
+                None => i,
+            };
+            if result[position].is_some(){
+                return Err(format!("the argument '{}' of '{}' is given more than once", signature.arg_names[position], self.name));
+            }
+            result[position] = Some(i);
+        }
+        for position in 0..count{
+            if result[position].is_none() && signature.defaults[position].is_none(){
+                return Err(format!("the call of '{}' is missing the argument '{}'", self.name, signature.arg_names[position]));
+            }
+        }
+        return Ok(result);
+    }
+
+    // puts the named arguments in their places and fills in the default values of the missing ones,
+    // so the C call only has positional arguments
+    fn order_arguments(&mut self, signature: &FunctionSignature, arg_types: Vec<VarType>) -> Result<Vec<VarType>, String>{
+        let arrangement = self.arrange_arguments(signature)?;
+        let mut args: Vec<Option<Box<Node>>> = std::mem::take(&mut self.args).into_iter().map(Some).collect();
+        let mut result: Vec<VarType> = Vec::new();
+        for (position, given) in arrangement.iter().enumerate(){
+            match given{
+                Some(i) => {
+                    self.args.push(args[*i].take().unwrap());
+                    result.push(arg_types[*i].clone());
+                },
+                None => {
+                    self.args.push(Box::new(signature.defaults[position].clone().unwrap()));
+                    result.push(signature.arg_types[position].clone());
+                },
+            }
+        }
+        self.arg_names = vec![None; result.len()];
+        return Ok(result);
+    }
+
+    // the overload whose given arguments are the cheapest to convert wins, the default values don't count
+    fn select_overload(&self, overloads: &Vec<FunctionSignature>, arg_types: &Vec<VarType>) -> Result<usize, String>{
+        if overloads.len() == 1 {return Ok(0);}
+        let mut best: Vec<usize> = Vec::new();
+        let mut best_cost = u32::MAX;
+        for (i, overload) in overloads.iter().enumerate(){
+            let arrangement = match self.arrange_arguments(overload){
+                Ok(arrangement) => arrangement,
+                Err(_) => continue,
+            };
+            let cost: Option<u32> = arrangement
+                .iter()
+                .enumerate()
+                .filter_map(|(position, given)| given.map(|given| conversion_cost(&arg_types[given], &overload.arg_types[position])))
+                .sum();
+            match cost{
+                Some(cost) if cost < best_cost => {best = vec![i]; best_cost = cost;},
+                Some(cost) if cost == best_cost => best.push(i),
+                _ => (),
+            }
+        }
+
+        match best.len(){
+            0 => return Err(format!("no overload of '{}' takes arguments of type ({})", self.name, type_list(arg_types))),
+            1 => return Ok(best[0]),
+            _ => {
+                let overloads: Vec<String> = best
+                    .iter()
+                    .map(|i| format!("{}({})", self.name, type_list(&overloads[*i].arg_types)))
+                    .collect();
+                return Err(format!("the call {}({}) is ambiguous between {}", self.name, type_list(arg_types), overloads.join(" and ")));
+            },
         }
+    }
+
+    pub fn to_c(&self) -> String{
+        if let Some(lowering) = &self.lowering {return lowering.to_c(&self.args);}
+        if let Some(literal_type) = &self.literal_type {return self.literal_to_c(literal_type);}
 
+        // the closures are called through the helper of their type, which passes them their environment
+        let mut result: String = self.c_name.to_owned();
+        if self.callee.is_some(){
+            result = format!("{}_call", self.callee_type.to_c().trim());
+        }
         result.push_str("(");
+        if let Some(callee) = &self.callee{
+            result.push_str(&callee.to_c());
+            if !self.args.is_empty() {result.push_str(", ");}
+        }
         for i in 0..self.args.len(){
             result.push_str(&self.args[i].to_c().to_owned());
             if i != self.args.len() - 1 {result.push_str(", ");}
@@ -106,34 +508,202 @@ impl NodeFunctionCall{
         result.push_str(")");
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let mut arg_types: Vec<VarType> = Vec::new();
+        for arg in self.args.iter_mut(){
+            arg_types.push(arg.analyze(table)?);
+        }
+
+        if let Some(callee) = self.callee.as_mut(){
+            let callee_type = callee.analyze(table)?;
+            return self.analyze_indirect(callee_type, arg_types);
+        }
+        // a variable holding a function - "callback(x)"
+        let mut callee = NodeVariableCall::new(self.name.to_string());
+        if let Ok(callee_type) = callee.analyze(table){
+            if matches!(callee_type, VarType::Function(_, _)) && !callee.is_function(){
+                if self.arg_names.iter().any(|name| name.is_some()){
+                    return Err(format!("'{}' holds a function, so it cannot be called with named arguments", self.name));
+                }
+                self.callee = Some(Box::new(Node::VariableCall(callee)));
+                return self.analyze_indirect(callee_type, arg_types);
+            }
+            // the local variables hide the functions in C too
+            if table.get_variable(&self.name).is_ok(){
+                return Err(format!("'{}' is a variable of type {}, so it cannot be called", self.name, callee_type));
+            }
+        }
+
+        if let Ok(struct_type) = table.resolve_type(&VarType::Struct(self.name.to_string())){
+            return self.analyze_struct_literal(table, struct_type, arg_types);
+        }
+        if let Some((enum_type, variant)) = table.get_variant(&self.name){
+            return self.analyze_variant_literal(table, enum_type, variant, arg_types);
+        }
+        if builtins::is_builtin(&self.name) && self.arg_names.iter().any(|name| name.is_some()){
+            return Err(format!("'{}' cannot be called with named arguments", self.name));
+        }
+
+        if let Some(builtin) = builtins::get_generic(&self.name, &arg_types){
+            for i in 0..arg_types.len(){
+                arg_types[i] = self.args[i].coerce(arg_types[i].clone(), &builtin.arg_types[i]);
+            }
+            check_arguments(&self.name, &builtin.arg_types, &arg_types)?;
+            if matches!(builtin.lowering, Lowering::Method(_)) && !self.args[0].is_lvalue(){
+                return Err(format!("the first argument of '{}' must be assignable", self.name));
+            }
+            if let Some(library) = builtin.library {table.link(library);}
+            self.lowering = Some(builtin.lowering);
+            return Ok(builtin.return_type);
+        }
+        if let Some(overloads) = builtins::get(&self.name){
+            let candidates: Vec<Vec<VarType>> = overloads.iter().map(|builtin| builtin.arg_types.clone()).collect();
+            let builtin = &overloads[resolve_overload(&self.name, &candidates, &arg_types)?];
+            if let Some(library) = builtin.library {table.link(library);}
+            self.lowering = Some(builtin.lowering.clone());
+            return Ok(builtin.return_type.clone());
+        }
+        if builtins::is_builtin(&self.name){
+            return Err(format!("'{}' cannot take arguments of type ({})", self.name, type_list(&arg_types)));
+        }
+
+        if let Some(generic) = table.get_generic(&self.name){
+            return self.analyze_generic(table, generic, arg_types);
+        }
+        let overloads = table.get_functions(&self.name)?;
+        let signature = overloads[self.select_overload(&overloads, &arg_types)?].clone();
+        arg_types = self.order_arguments(&signature, arg_types)?;
+        for i in 0..arg_types.len().min(signature.arg_types.len()){
+            arg_types[i] = self.args[i].coerce(arg_types[i].clone(), &signature.arg_types[i]);
+        }
+        check_arguments(&self.name, &signature.arg_types, &arg_types)?;
+        self.c_name = signature.c_name;
+        return Ok(signature.return_type);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeReturn{
+    value: Option<Box<Node>>,
+    // set by the semantic pass - the deferred statements run once the value is computed
+    cleanup: Vec<Node>,
+    return_type: VarType,
+}
+
+impl NodeReturn{
+    pub fn new(value: Option<Box<Node>>) -> Self{
+        NodeReturn {value: value, cleanup: Vec::new(), return_type: VarType::None}
+    }
+
+    pub fn to_c(&self) -> String{
+        if !self.cleanup.is_empty(){
+            let mut result: String = "{\n".to_owned();
+            if let Some(value) = &self.value{
+                result.push_str(&format!("{}__return = {};\n", self.return_type.to_c(), value.to_c()));
+            }
+            result.push_str(&cleanup_to_c(&self.cleanup));
+            result.push_str(if self.value.is_some() {"return __return;\n}"} else {"return;\n}"});
+            return result;
+        }
+        let mut result: String = "return".to_owned();
+        if let Some(value) = &self.value{
+            result.push(' ');
+            result.push_str(&value.to_c());
+        }
+        return result;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let return_type = match &table.return_type{
+            Some(return_type) => return_type.clone(),
+            None => return Err("'return' outside of a function".to_string()),
+        };
+        let mut value_type = match self.value.as_mut(){
+            Some(value) => value.analyze(table)?,
+            None => VarType::None,
+        };
+        if let Some(value) = self.value.as_mut() {value_type = value.coerce(value_type, &return_type);}
+        if value_type == VarType::None && return_type != VarType::None{
+            return Err(format!("the function must return a value of type {}", return_type));
+        }
+        if !is_compatible(&value_type, &return_type){
+            return Err(format!("cannot return a value of type {} from a function returning {}", value_type, return_type));
+        }
+        self.cleanup = table.deferred(table.function_scope());
+        self.return_type = return_type;
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeReturn{
+    fn from(tokens: &Vec<Token>) -> Self{
+        if tokens.len() == 1 {return NodeReturn::new(None);}
+        return NodeReturn::new(Some(Box::new(generate_expression(&tokens[1..].to_vec()))));
+    }
+}
+
+// "(name: type, name: type = value)" - moves the index to the closing parenthesis
+fn parse_arguments(tokens: &Vec<Token>, i: &mut usize) -> (Vec<String>, Vec<VarType>, Vec<Option<Box<Node>>>){
+    let mut arg_names: Vec<String> = Vec::new();
+    let mut arg_types: Vec<VarType> = Vec::new();
+    let mut defaults: Vec<Option<Box<Node>>> = Vec::new();
+    let closing = *i + get_closing(&tokens[*i..]);
+    for arg in split_arguments(&tokens[*i + 1 .. closing]){
+        arg_names.push(get_token_value!(&arg[0], Token::Identifier).expect("expected the name of an argument").to_string());
+        let mut j = 2;
+        arg_types.push(parse_type(&arg, &mut j));
+        match arg.get(j){
+            Some(Token::Eq) => defaults.push(Some(Box::new(generate_expression(&arg[j + 1 ..].to_vec())))),
+            Some(token) => panic!("unexpected {:?} after the type of argument '{}'", token, arg_names.last().unwrap()),
+            None => defaults.push(None),
+        }
+    }
+    *i = closing;
+    return (arg_names, arg_types, defaults);
 }
 
 pub fn generate_function(tokens: &Vec<Token>) -> Node{
-    println!("function tokens = {:#?}\n", tokens);
     let mut i = 0;
     let mut result: NodeFunctionDefinition = NodeFunctionDefinition {
         name: "".to_string(), 
+        c_name: "".to_string(),
+        type_params: Vec::new(),
         arg_names: Vec::new(), 
         arg_types: Vec::new(),
+        defaults: Vec::new(),
         body: Vec::new(), 
-        return_type: VarType::None
+        return_type: VarType::None,
+        external: false,
+        exported: false,
+        declared: false,
     };
 
     i += 1; 
     result.name = get_token_value!(&tokens[i], Token::Identifier).unwrap().to_string();
-    i += 2; // this is so we skip the opening bracket - '('
+    i += 1;
 
-    while tokens[i] != Token::RPar{
-        result.arg_names.push(get_token_value!(&tokens[i], Token::Identifier).unwrap().to_string());
-        i += 2;
-        result.arg_types.push(VarType::from(get_token_value!(tokens[i], Token::Keyword).unwrap()));
-        i += 1;
-        variables_insert(&result.arg_names[result.arg_names.len() - 1], &result.arg_types[result.arg_types.len() - 1]);
+    // "[T, U: numeric]"
+    if tokens[i] == Token::LBracket{
+        let closing = i + get_closing(&tokens[i..]);
+        for type_param in split_arguments(&tokens[i + 1 .. closing]){
+            let name: String = get_token_value!(&type_param[0], Token::Identifier).expect("expected the name of a type parameter").to_string();
+            let constraint: Option<String> = match (type_param.get(1), type_param.get(2)){
+                (Some(Token::Colon), Some(Token::Identifier(constraint))) => Some(constraint.to_string()),
+                (None, _) => None,
+                _ => panic!("expected a constraint after the type parameter '{}'", name),
+            };
+            result.type_params.push((name, constraint));
+        }
+        i = closing + 1;
     }
 
+    (result.arg_names, result.arg_types, result.defaults) = parse_arguments(tokens, &mut i);
+
     i += 2; // here we skip the ')' and '=>' 
-    result.return_type = VarType::from(get_token_value!(tokens[i], Token::Keyword).unwrap()); 
-    i += 1;
+    result.return_type = parse_type(tokens, &mut i); 
+    // the C functions declared with 'extern' end with their return type
+    if i == tokens.len() {return Node::FunctionDefinition(result);}
     if tokens[i] == Token::Colon {i += 1;}
 
     let split_body_tokens = split_tokens(tokens[i .. tokens.len() - 1].to_vec());
@@ -155,3 +725,170 @@ pub fn generate_function(tokens: &Vec<Token>) -> Node{
     return Node::FunctionDefinition(result);
 }
 
+// "extern fn name(arguments) -> type" - a C function, called by its own name
+pub fn generate_extern(tokens: &Vec<Token>) -> Node{
+    if tokens.last() == Some(&Token::Colon) {panic!("a function declared with 'extern' cannot have a body");}
+    match generate_function(&tokens[1..].to_vec()){
+        Node::FunctionDefinition(mut function) => {
+            function.external = true;
+            return Node::FunctionDefinition(function);
+        },
+        node => return node,
+    }
+}
+
+// "export fn name(arguments) -> type:" - a function the C code using the library can call
+pub fn generate_export(tokens: &Vec<Token>) -> Node{
+    if tokens.get(1) != Some(&Token::Keyword(Keyword::Fn)) {panic!("only functions can be exported");}
+    match generate_function(&tokens[1..].to_vec()){
+        Node::FunctionDefinition(mut function) => {
+            function.exported = true;
+            return Node::FunctionDefinition(function);
+        },
+        node => return node,
+    }
+}
+
+
+// the C value of a function used as a value - a closure calling it through a wrapper
+// which ignores the environment - "((__fn_i32__i32){__fnwrap_twice, NULL})"
+pub fn function_value_to_c(signature: &FunctionSignature) -> String{
+    let wrapper: String = format!("__fnwrap_{}", signature.c_name);
+    let mut params: String = "void* __env".to_string();
+    let mut args: Vec<String> = Vec::new();
+    for (i, arg_type) in signature.arg_types.iter().enumerate(){
+        params.push_str(&format!(", {}__arg{}", arg_type.to_c(), i));
+        args.push(format!("__arg{}", i));
+    }
+    let call: &str = if signature.return_type == VarType::None {""} else {"return "};
+    runtime::declare_function(
+        &wrapper,
+        format!("{}{}({})", signature.return_type.to_c(), wrapper, params),
+        format!("{{\n{}{}({});\n}}\n", call, signature.c_name, args.join(", ")),
+    );
+    let function_type = VarType::Function(signature.arg_types.clone(), Box::new(signature.return_type.clone()));
+    return format!("(({}){{{}, NULL}})", function_type.to_c().trim(), wrapper);
+}
+
+// numbers the lambdas of the whole program, which are lifted out of their functions
+static LAMBDAS: AtomicUsize = AtomicUsize::new(0);
+
+// an anonymous function - "fn(x: i32) -> i32: x * k end" - the variables of the enclosing functions
+// it uses are copied to its environment when it is made, so it can outlive them
+#[derive(Debug, Clone)]
+pub struct NodeLambda{
+    c_name: String, // "__lambda_1", set by the semantic pass
+    arg_names: Vec<String>,
+    arg_types: Vec<VarType>,
+    return_type: VarType,
+    body: Vec<Box<Node>>,
+    // set by the semantic pass - the captured variables and their values where the lambda is made
+    captures: Vec<(String, VarType)>,
+    capture_values: Vec<String>,
+    function_type: VarType,
+}
+
+impl NodeLambda{
+    // the lambda becomes a C function taking its environment first, the lambda expression
+    // makes the closure - "__lambda_1_new(k)", or just the compound literal when nothing is captured
+    pub fn to_c(&self) -> String{
+        let function_type: String = self.function_type.to_c().trim().to_string();
+        let env: String = format!("{}_env", self.c_name);
+
+        let mut signature: String = format!("{}{}(void* __env_ptr", self.return_type.to_c(), self.c_name);
+        for i in 0..self.arg_names.len(){
+            signature.push_str(", ");
+            signature.push_str(&self.arg_types[i].to_c());
+            signature.push_str(&self.arg_names[i]);
+        }
+        signature.push(')');
+        let mut body: String = "{\n".to_string();
+        if !self.captures.is_empty() {body.push_str(&format!("{}* __env = __env_ptr;\n", env));}
+        body.push_str(&body_to_c(&self.body));
+        body.push_str("}\n");
+        runtime::declare_function(&self.c_name, signature, body);
+
+        if self.captures.is_empty() {return format!("(({}){{{}, NULL}})", function_type, self.c_name);}
+        let mut fields: String = String::new();
+        let mut params: Vec<String> = Vec::new();
+        let mut constructor: String = format!("{{\n{}* __env = malloc(sizeof({}));\n", env, env);
+        for (name, var_type) in &self.captures{
+            fields.push_str(&format!("{}{}; ", var_type.to_c(), name));
+            params.push(format!("{}{}", var_type.to_c(), name));
+            constructor.push_str(&format!("__env->{} = {};\n", name, name));
+        }
+        constructor.push_str(&format!("return (({}){{{}, __env}});\n}}\n", function_type, self.c_name));
+        runtime::declare_type(&env, format!("typedef struct {{ {}}} {};\n", fields, env));
+        runtime::declare_function(&format!("{}_new", self.c_name), format!("{} {}_new({})", function_type, self.c_name, params.join(", ")), constructor);
+        return format!("{}_new({})", self.c_name, self.capture_values.join(", "));
+    }
+
+    // the lambdas are named when they are analyzed, as the instances of a generic function
+    // analyze their own copies of its lambdas
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.c_name = format!("__lambda_{}", LAMBDAS.fetch_add(1, Ordering::Relaxed) + 1);
+        for i in 0..self.arg_types.len(){
+            self.arg_types[i] = table.resolve_type(&self.arg_types[i])?;
+        }
+        self.return_type = table.resolve_type(&self.return_type)?;
+        let enclosing_return_type = table.return_type.replace(self.return_type.clone());
+        table.enter_lambda();
+        for i in 0..self.arg_names.len(){
+            table.insert_variable(&self.arg_names[i], &self.arg_types[i])?;
+        }
+        for node in self.body.iter_mut(){
+            node.analyze(table)?;
+        }
+        self.captures = table.exit_lambda();
+        table.return_type = enclosing_return_type;
+        if self.return_type != VarType::None && !always_returns(&self.body){
+            return Err(format!("a lambda does not return a value of type {} on every path", self.return_type));
+        }
+
+        // a lambda nested in another one gets the values from the environment of the outer one
+        self.capture_values = self.captures
+            .iter()
+            .map(|(name, _var_type)| if table.is_captured(name) {format!("__env->{}", name)} else {name.to_string()})
+            .collect();
+        self.function_type = VarType::Function(self.arg_types.clone(), Box::new(self.return_type.clone()));
+        return Ok(self.function_type.clone());
+    }
+}
+
+impl From<&Vec<Token>> for NodeLambda{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut i = 1;
+        let (arg_names, arg_types, defaults) = parse_arguments(tokens, &mut i);
+        if defaults.iter().any(|default| default.is_some()) {panic!("the arguments of a lambda cannot have default values");}
+        i += 1;
+        let mut return_type = VarType::None;
+        if tokens[i] == Token::Return{
+            i += 1;
+            return_type = parse_type(tokens, &mut i);
+        }
+        if tokens[i] != Token::Colon {panic!("expected ':' after the signature of a lambda");}
+
+        // the body may be on the same line as the signature
+        let mut body_tokens: Vec<Token> = vec![Token::NewLine];
+        body_tokens.extend_from_slice(&tokens[i + 1 .. tokens.len() - 1]);
+        body_tokens.push(Token::NewLine);
+        let mut body: Vec<Box<Node>> = split_tokens(body_tokens).into_iter().map(|statement| Box::new(Node::from(statement))).collect();
+
+        // "fn(x: i32) -> i32: x * k end" returns the value of its only expression
+        if body.len() == 1 && return_type != VarType::None && body[0].is_expression(){
+            let value = body.remove(0);
+            body.push(Box::new(Node::Return(NodeReturn::new(Some(value)))));
+        }
+
+        return NodeLambda {
+            c_name: String::new(),
+            arg_names: arg_names,
+            arg_types: arg_types,
+            return_type: return_type,
+            body: body,
+            captures: Vec::new(),
+            capture_values: Vec::new(),
+            function_type: VarType::None,
+        };
+    }
+}
@@ -0,0 +1,228 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+use super::generate_expression;
+
+// a value known at compile time - the constants are folded and the globals are initialized with these
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue{
+    Int(i128),
+    Float(f64),
+    Str(String),    // the C literal, with its quotes
+    Symbol(String), // a C constant nothing is computed with, like an enum variant
+}
+
+impl ConstValue{
+    pub fn to_c(&self) -> String{
+        match self{
+            // the literals C can't write as they are - "-9223372036854775808" is the negation of a
+            // literal too big for a long long, and the numbers past its maximum are unsigned
+            ConstValue::Int(val) if *val == i64::MIN as i128 => return "(-9223372036854775807LL - 1)".to_string(),
+            ConstValue::Int(val) if *val > i64::MAX as i128 => return format!("{}ULL", val),
+            ConstValue::Int(val)    => return val.to_string(),
+            ConstValue::Float(val)  => return format!("{:?}", val),
+            ConstValue::Str(val)    => return val.to_string(),
+            ConstValue::Symbol(val) => return val.to_string(),
+        }
+    }
+
+    fn as_float(&self) -> Option<f64>{
+        match self{
+            ConstValue::Int(val)   => return Some(*val as f64),
+            ConstValue::Float(val) => return Some(*val),
+            _ => return None,
+        }
+    }
+
+    fn is_true(&self) -> Option<bool>{
+        match self{
+            ConstValue::Int(val)   => return Some(*val != 0),
+            ConstValue::Float(val) => return Some(*val != 0.0),
+            _ => return None,
+        }
+    }
+
+    // the value a variable of the given type holds - None if it doesn't fit in it, as C would
+    // wrap it around (or make an infinity of it) without a word
+    pub fn convert(self, var_type: &VarType) -> Option<ConstValue>{
+        let value: i128 = match self{
+            ConstValue::Int(val) if is_float(var_type) => return Some(ConstValue::Float(val as f64)),
+            ConstValue::Float(val) if *var_type == VarType::F32 => {
+                let converted: f64 = val as f32 as f64;
+                if !converted.is_finite() {return None;}
+                return Some(ConstValue::Float(converted));
+            },
+            ConstValue::Float(val) if is_float(var_type) => {
+                if !val.is_finite() {return None;}
+                return Some(ConstValue::Float(val));
+            },
+            ConstValue::Float(val) if is_numeric(var_type) => {
+                if !val.is_finite() {return None;}
+                val.trunc() as i128
+            },
+            ConstValue::Int(val) => val,
+            _ => return Some(self),
+        };
+        let (min, max): (i128, i128) = match var_type{
+            VarType::I8  => (i8::MIN as i128, i8::MAX as i128),
+            VarType::I16 => (i16::MIN as i128, i16::MAX as i128),
+            VarType::I32 => (i32::MIN as i128, i32::MAX as i128),
+            VarType::I64 => (i64::MIN as i128, i64::MAX as i128),
+            VarType::U8  => (0, u8::MAX as i128),
+            VarType::U16 => (0, u16::MAX as i128),
+            VarType::U32 => (0, u32::MAX as i128),
+            VarType::U64 => (0, u64::MAX as i128),
+            VarType::Bool => return Some(ConstValue::Int((value != 0) as i128)),
+            _ => return Some(ConstValue::Int(value)),
+        };
+        if value < min || value > max {return None;}
+        return Some(ConstValue::Int(value));
+    }
+
+    pub fn unary(operator: &OperatorType, value: ConstValue) -> Option<ConstValue>{
+        match (operator, value){
+            (OperatorType::Minus, ConstValue::Int(val))   => return Some(ConstValue::Int(val.checked_neg()?)),
+            (OperatorType::Minus, ConstValue::Float(val)) => return Some(ConstValue::Float(-val)),
+            (OperatorType::Not, value) => return Some(ConstValue::Int(!value.is_true()? as i128)),
+            _ => return None,
+        }
+    }
+
+    // the floats are only used when one of the values is a float, like in C - a division by zero
+    // or a result too big for the compiler can't be folded
+    pub fn binary(operator: &OperatorType, left: ConstValue, right: ConstValue) -> Option<ConstValue>{
+        match operator{
+            OperatorType::And => return Some(ConstValue::Int((left.is_true()? && right.is_true()?) as i128)),
+            OperatorType::Or  => return Some(ConstValue::Int((left.is_true()? || right.is_true()?) as i128)),
+            _ => (),
+        }
+        if let (ConstValue::Int(left), ConstValue::Int(right)) = (&left, &right){
+            let (left, right) = (*left, *right);
+            let result: i128 = match operator{
+                OperatorType::Plus  => left.checked_add(right)?,
+                OperatorType::Minus => left.checked_sub(right)?,
+                OperatorType::Mul   => left.checked_mul(right)?,
+                OperatorType::Div   => left.checked_div(right)?,
+                OperatorType::Mod   => left.checked_rem(right)?,
+                OperatorType::EqEq  => (left == right) as i128,
+                OperatorType::NotEq => (left != right) as i128,
+                OperatorType::Lt    => (left < right) as i128,
+                OperatorType::Gt    => (left > right) as i128,
+                OperatorType::LtEq  => (left <= right) as i128,
+                OperatorType::GtEq  => (left >= right) as i128,
+                _ => return None,
+            };
+            return Some(ConstValue::Int(result));
+        }
+        let (left, right) = (left.as_float()?, right.as_float()?);
+        match operator{
+            OperatorType::Plus  => return Some(ConstValue::Float(left + right)),
+            OperatorType::Minus => return Some(ConstValue::Float(left - right)),
+            OperatorType::Mul   => return Some(ConstValue::Float(left * right)),
+            OperatorType::Div   => return Some(ConstValue::Float(left / right)),
+            OperatorType::EqEq  => return Some(ConstValue::Int((left == right) as i128)),
+            OperatorType::NotEq => return Some(ConstValue::Int((left != right) as i128)),
+            OperatorType::Lt    => return Some(ConstValue::Int((left < right) as i128)),
+            OperatorType::Gt    => return Some(ConstValue::Int((left > right) as i128)),
+            OperatorType::LtEq  => return Some(ConstValue::Int((left <= right) as i128)),
+            OperatorType::GtEq  => return Some(ConstValue::Int((left >= right) as i128)),
+            _ => return None,
+        }
+    }
+}
+
+// a variable defined at the top level of a module - "const NAME: type = value" or "type name = value"
+#[derive(Debug, Clone)]
+pub struct NodeGlobal{
+    name: String,
+    c_name: String, // set by the semantic pass
+    var_type: VarType,
+    value: Option<Box<Node>>,
+    is_constant: bool,
+    folded: Option<ConstValue>,
+}
+
+impl NodeGlobal{
+    pub fn to_c(&self) -> String{
+        let mut result: String = "__CHAL_LOCAL ".to_string();
+        if self.is_constant {result.push_str("const ");}
+        result.push_str(&self.var_type.to_c());
+        result.push_str(&self.c_name);
+        result.push_str(" = ");
+        match &self.folded{
+            Some(value) => result.push_str(&value.to_c()),
+            None => result.push_str(&self.var_type.to_c_default_value()),
+        }
+        result.push_str(";\n");
+        return result;
+    }
+
+    // the globals are declared in the order they are written, before the functions are analyzed -
+    // the C globals can only be initialized with constants, so every value has to be folded
+    pub fn declare(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        if self.var_type != VarType::Auto {self.var_type = table.resolve_type(&self.var_type)?;}
+        if let Some(value) = &mut self.value{
+            let mut value_type = value.analyze(table)?;
+            if value_type == VarType::None{
+                return Err(format!("cannot initialize '{}' with a value of type none", self.name));
+            }
+            if self.var_type == VarType::Auto{
+                self.var_type = value_type.clone();
+            }
+            value_type = value.coerce(value_type, &self.var_type);
+            if !is_compatible(&value_type, &self.var_type){
+                return Err(format!("cannot initialize '{}' of type {} with a value of type {}", self.name, self.var_type, value_type));
+            }
+            let folded: ConstValue = value.fold().ok_or(format!(
+                "the value of '{}' must be known at compile time, without dividing by zero or overflowing", self.name
+            ))?;
+            let shown: String = match &folded {ConstValue::Int(val) => val.to_string(), value => value.to_c()};
+            self.folded = Some(folded.convert(&self.var_type).ok_or(format!(
+                "the value of '{}', {}, does not fit in {}", self.name, shown, self.var_type
+            ))?);
+        }else if self.var_type == VarType::Auto{
+            return Err(format!("cannot infer the type of '{}' without a value", self.name));
+        }
+
+        let constant: Option<ConstValue> = if self.is_constant {self.folded.clone()} else {None};
+        self.c_name = table.insert_global(&self.name, &self.var_type, constant)?;
+        return Ok(());
+    }
+
+    pub fn analyze(&mut self, _table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.c_name.is_empty(){
+            return Err("constants can only be defined at the top level of a module".to_string());
+        }
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeGlobal{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut result = NodeGlobal {
+            name: String::new(),
+            c_name: String::new(),
+            var_type: VarType::Auto,
+            value: None,
+            is_constant: tokens[0] == Token::Keyword(Keyword::Const),
+            folded: None,
+        };
+        let mut i = 0;
+        if result.is_constant{
+            result.name = get_token_value!(&tokens[1], Token::Identifier).expect("expected the name of the constant").to_string();
+            if tokens.get(2) != Some(&Token::Colon) {panic!("expected ':' after the name of constant '{}'", result.name);}
+            i = 3;
+            result.var_type = parse_type(tokens, &mut i);
+            if tokens.get(i) != Some(&Token::Eq) {panic!("constant '{}' needs a value", result.name);}
+        }else{
+            result.var_type = parse_type(tokens, &mut i);
+            result.name = get_token_value!(&tokens[i], Token::Identifier).unwrap().to_string();
+            i += 1;
+        }
+        if i + 1 < tokens.len(){
+            result.value = Some(Box::new(generate_expression(&tokens[i + 1..].to_vec())));
+        }
+        return result;
+    }
+}
@@ -0,0 +1,70 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+
+#[derive(Debug, Clone)]
+pub enum ImportSource{
+    Path(String), // import "path/module.ch"
+    Name(String), // use module
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeImport{
+    pub source: ImportSource,
+    pub module: String, // the name of the imported module, set by the module loader
+}
+
+impl NodeImport{
+    pub fn to_c(&self) -> String{
+        "".to_string()
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.module.is_empty(){
+            return Err("imports are only allowed at the top level of a module".to_string());
+        }
+        table.import(&self.module);
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeImport{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let source = match (&tokens[0], &tokens[1]){
+            (Token::Keyword(Keyword::Import), Token::String(path)) => ImportSource::Path(path[1 .. path.len() - 1].to_string()),
+            (Token::Keyword(Keyword::Use), Token::Identifier(name)) => ImportSource::Name(name.to_string()),
+            _ => panic!("expected 'import \"path/module.ch\"' or 'use module'"),
+        };
+        NodeImport {source: source, module: String::new()}
+    }
+}
+
+// extern "header.h" - the C header declaring the 'extern' functions, included by the generated
+// C file ("extern "<sys/stat.h>"" for the system headers)
+#[derive(Debug, Clone)]
+pub struct NodeInclude{
+    header: String,
+}
+
+impl NodeInclude{
+    pub fn to_c(&self) -> String{
+        "".to_string()
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if table.return_type.is_some(){
+            return Err("headers are only included at the top level of a module".to_string());
+        }
+        table.include(&self.header);
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeInclude{
+    fn from(tokens: &Vec<Token>) -> Self{
+        match (&tokens[1], tokens.len()){
+            (Token::String(header), 2) => return NodeInclude {header: header[1 .. header.len() - 1].to_string()},
+            _ => panic!("expected 'extern \"header.h\"' or 'extern fn name(arguments) -> type'"),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+use super::variables::NodeVariableCall;
+
+// "c(x, y -> z): ... end" - C code copied to the generated program as it is, which can only use the
+// variables it is given: it reads copies of the inputs, and the outputs are written back after it
+#[derive(Debug, Clone)]
+pub struct NodeInlineC{
+    code: String,
+    inputs: Vec<NodeVariableCall>,
+    outputs: Vec<NodeVariableCall>,
+    // set by the semantic pass
+    input_types: Vec<VarType>,
+    output_types: Vec<VarType>,
+}
+
+impl NodeInlineC{
+    // the bindings go through temporaries, as "long x = x;" would not read the variable outside -
+    // "{ long __c_in0 = x; { long x = __c_in0; ... } }"
+    pub fn to_c(&self) -> String{
+        let mut outer: String = "{\n".to_string();
+        let mut inner: String = "{\n".to_string();
+        for (i, input) in self.inputs.iter().enumerate(){
+            outer.push_str(&format!("{}__c_in{} = {};\n", self.input_types[i].to_c(), i, input.to_c()));
+            inner.push_str(&format!("{}{} = __c_in{};\n", self.input_types[i].to_c(), input.name(), i));
+        }
+        for (i, output) in self.outputs.iter().enumerate(){
+            outer.push_str(&format!("{}__c_out{} = {};\n", self.output_types[i].to_c(), i, output.to_c()));
+            inner.push_str(&format!("{}{} = __c_out{};\n", self.output_types[i].to_c(), output.name(), i));
+        }
+        inner.push_str(&self.code);
+        for (i, output) in self.outputs.iter().enumerate(){
+            inner.push_str(&format!("__c_out{} = {};\n", i, output.name()));
+        }
+        inner.push_str("}\n");
+        outer.push_str(&inner);
+        for (i, output) in self.outputs.iter().enumerate(){
+            outer.push_str(&format!("{} = __c_out{};\n", output.to_c(), i));
+        }
+        outer.push_str("}");
+        return outer;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if table.return_type.is_none(){
+            return Err("inline C outside of a function".to_string());
+        }
+        self.input_types = Vec::new();
+        for variable in self.inputs.iter_mut(){
+            self.input_types.push(bind(variable, table)?);
+        }
+        self.output_types = Vec::new();
+        for variable in self.outputs.iter_mut(){
+            self.output_types.push(bind(variable, table)?);
+            if !variable.is_assignable(){
+                return Err(format!("inline C cannot write to the constant '{}'", variable.name()));
+            }
+        }
+        return Ok(VarType::None);
+    }
+}
+
+fn bind(variable: &mut NodeVariableCall, table: &mut SymbolTable) -> Result<VarType, String>{
+    let var_type = variable.analyze(table)?;
+    if variable.is_function(){
+        return Err(format!("'{}' is a function, inline C can only be given variables", variable.name()));
+    }
+    return Ok(var_type);
+}
+
+impl From<&Vec<Token>> for NodeInlineC{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let code: String = tokens.iter().find_map(|token| get_token_value!(token, Token::CCode).cloned()).unwrap();
+        let mut inputs: Vec<NodeVariableCall> = Vec::new();
+        let mut outputs: Vec<NodeVariableCall> = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        if tokens[1] == Token::LPar{
+            let mut writing: bool = false;
+            for token in &tokens[2..]{
+                match token{
+                    Token::RPar => break,
+                    Token::Comma => continue,
+                    Token::Return if !writing => writing = true,
+                    Token::Identifier(name) => {
+                        if names.contains(name) {panic!("'{}' is given to the inline C block more than once", name);}
+                        names.push(name.to_string());
+                        let variable = NodeVariableCall::new(name.to_string());
+                        if writing {outputs.push(variable);} else {inputs.push(variable);}
+                    },
+                    token => panic!("unexpected {:?} in the variables of an inline C block", token),
+                }
+            }
+        }
+        return NodeInlineC {code: code, inputs: inputs, outputs: outputs, input_types: Vec::new(), output_types: Vec::new()};
+    }
+}
@@ -0,0 +1,44 @@
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+
+// "alloc(count)" - room on the heap for that many values of the type the pointer it is assigned
+// to points to, zeroed, until it is given to free()
+#[derive(Debug, Clone)]
+pub struct NodeAlloc{
+    count: Box<Node>,
+    var_type: VarType, // set by the semantic pass
+}
+
+impl NodeAlloc{
+    pub fn new(count: Node) -> Self{
+        NodeAlloc {count: Box::new(count), var_type: VarType::None}
+    }
+
+    pub fn to_c(&self) -> String{
+        let element: String = match &self.var_type{
+            VarType::Pointer(element) => element.to_c(),
+            _ => String::new(),
+        };
+        return format!("(({})__chal_alloc({}, sizeof({})))", self.var_type.to_c(), self.count.to_c(), element.trim());
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let count_type = self.count.analyze(table)?;
+        if !is_numeric(&count_type) || is_float(&count_type){
+            return Err(format!("the number of values to allocate must be an integer, found {}", count_type));
+        }
+        self.var_type = VarType::Pointer(Box::new(VarType::Auto));
+        return Ok(self.var_type.clone());
+    }
+
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        match target{
+            VarType::Pointer(element) if **element != VarType::None => {
+                self.var_type = target.clone();
+                return self.var_type.clone();
+            },
+            _ => return value_type,
+        }
+    }
+}
@@ -4,6 +4,16 @@ pub mod binary_expression;
 pub mod unary_expression;
 pub mod control_flow;
 pub mod functions;
+pub mod string_interpolation;
+pub mod imports;
+pub mod arrays;
+pub mod structs;
+pub mod enums;
+pub mod globals;
+pub mod tuples;
+pub mod options;
+pub mod memory;
+pub mod inline_c;
 
 use values::*;
 use variables::*;
@@ -11,9 +21,20 @@ use binary_expression::*;
 use unary_expression::*;
 use control_flow::*;
 use functions::*;
+use string_interpolation::*;
+use imports::*;
+use arrays::*;
+use structs::*;
+use enums::*;
+use globals::*;
+use tuples::*;
+use options::*;
+use memory::*;
+use inline_c::*;
 
 use crate::lexer::*;
 use crate::parser::*;
+use crate::semantic::SymbolTable;
 
 #[derive(Debug, Clone)]
 pub enum Node{
@@ -21,6 +42,7 @@ pub enum Node{
     ValueUInt(NodeValueUInt),
     ValueFloat(NodeValueFloat),
     ValueString(NodeValueString),
+    InterpolatedString(NodeInterpolatedString),
     VariableCall(NodeVariableCall),
     VariableInitialization(NodeVariableInitialization), 
     VariableDeclaration(NodeVariableDeclaration), 
@@ -32,6 +54,25 @@ pub enum Node{
     WhileLoop(NodeWhileLoop), 
     FunctionDefinition(NodeFunctionDefinition),
     FunctionCall(NodeFunctionCall),
+    Lambda(NodeLambda),
+    Return(NodeReturn),
+    Import(NodeImport),
+    Include(NodeInclude),
+    ArrayLiteral(NodeArrayLiteral),
+    TupleLiteral(NodeTupleLiteral),
+    Wrapper(NodeWrapper),
+    Try(NodeTry),
+    Alloc(NodeAlloc),
+    Index(NodeIndex),
+    ForLoop(NodeForLoop),
+    StructDefinition(NodeStructDefinition),
+    FieldAccess(NodeFieldAccess),
+    EnumDefinition(NodeEnumDefinition),
+    Match(NodeMatch),
+    Jump(NodeJump),
+    Defer(NodeDefer),
+    Global(NodeGlobal),
+    InlineC(NodeInlineC),
     None,
     /*
     ForLoop{
@@ -55,6 +96,7 @@ impl Node{
             Node::ValueUInt(val)              => return val.to_c(),
             Node::ValueFloat(val)             => return val.to_c(),
             Node::ValueString(val)            => return val.to_c(),
+            Node::InterpolatedString(val)     => return val.to_c(),
             Node::VariableCall(val)           => return val.to_c(),
             Node::VariableInitialization(val) => return val.to_c(),
             Node::VariableDeclaration(val)    => return val.to_c(),
@@ -62,13 +104,126 @@ impl Node{
             Node::UnaryExpression(val)        => return val.to_c(),
             Node::FunctionDefinition(val)     => return val.to_c(),
             Node::FunctionCall(val)           => return val.to_c(),
+            Node::Lambda(val)                 => return val.to_c(),
+            Node::Return(val)                 => return val.to_c(),
             Node::IfStatement(val)            => return val.to_c(),
             Node::ElifStatement(val)          => return val.to_c(),
             Node::ElseStatement(val)          => return val.to_c(),
             Node::WhileLoop(val)              => return val.to_c(),
+            Node::Import(val)                 => return val.to_c(),
+            Node::Include(val)                => return val.to_c(),
+            Node::ArrayLiteral(val)           => return val.to_c(),
+            Node::TupleLiteral(val)           => return val.to_c(),
+            Node::Wrapper(val)                => return val.to_c(),
+            Node::Try(val)                    => return val.to_c(),
+            Node::Alloc(val)                  => return val.to_c(),
+            Node::Index(val)                  => return val.to_c(),
+            Node::ForLoop(val)                => return val.to_c(),
+            Node::StructDefinition(val)       => return val.to_c(),
+            Node::FieldAccess(val)            => return val.to_c(),
+            Node::EnumDefinition(val)         => return val.to_c(),
+            Node::Match(val)                  => return val.to_c(),
+            Node::Jump(val)                   => return val.to_c(),
+            Node::Defer(_val)                 => return String::new(), // written out by body_to_c()
+            Node::Global(val)                 => return val.to_c(),
+            Node::InlineC(val)                => return val.to_c(),
             _ => todo!(),
         }
     }
+
+    // checks the node and returns the type of the value it evaluates to
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        match self{
+            Node::ValueInt(val)               => return Ok(val.var_type.clone()),
+            Node::ValueUInt(val)              => return Ok(val.var_type.clone()),
+            Node::ValueFloat(val)             => return Ok(val.var_type.clone()),
+            Node::ValueString(val)            => return Ok(val.var_type.clone()),
+            Node::InterpolatedString(val)     => return val.analyze(table),
+            Node::VariableCall(val)           => return val.analyze(table),
+            Node::VariableInitialization(val) => return val.analyze(table),
+            Node::VariableDeclaration(val)    => return val.analyze(table),
+            Node::BinaryExpression(val)       => return val.analyze(table),
+            Node::UnaryExpression(val)        => return val.analyze(table),
+            Node::FunctionDefinition(val)     => return val.analyze(table),
+            Node::FunctionCall(val)           => return val.analyze(table),
+            Node::Lambda(val)                 => return val.analyze(table),
+            Node::Return(val)                 => return val.analyze(table),
+            Node::IfStatement(val)            => return val.analyze(table),
+            Node::ElifStatement(val)          => return val.analyze(table),
+            Node::ElseStatement(val)          => return val.analyze(table),
+            Node::WhileLoop(val)              => return val.analyze(table),
+            Node::Import(val)                 => return val.analyze(table),
+            Node::Include(val)                => return val.analyze(table),
+            Node::ArrayLiteral(val)           => return val.analyze(table),
+            Node::TupleLiteral(val)           => return val.analyze(table),
+            Node::Wrapper(val)                => return val.analyze(table),
+            Node::Try(val)                    => return val.analyze(table),
+            Node::Alloc(val)                  => return val.analyze(table),
+            Node::Index(val)                  => return val.analyze(table),
+            Node::ForLoop(val)                => return val.analyze(table),
+            Node::StructDefinition(val)       => return val.analyze(table),
+            Node::FieldAccess(val)            => return val.analyze(table),
+            Node::EnumDefinition(val)         => return val.analyze(table),
+            Node::Match(val)                  => return val.analyze(table),
+            Node::Jump(val)                   => return val.analyze(table),
+            Node::Defer(val)                  => return val.analyze(table),
+            Node::Global(val)                 => return val.analyze(table),
+            Node::InlineC(val)                => return val.analyze(table),
+            Node::None                        => return Ok(VarType::None),
+        }
+    }
+
+    // whether the node can be on the left side of an assignment
+    pub fn is_lvalue(&self) -> bool{
+        match self{
+            Node::VariableCall(val)  => return val.is_assignable(),
+            Node::Index(val)         => return val.array.is_lvalue(),
+            Node::FieldAccess(val)   => return val.object.is_lvalue(),
+            Node::UnaryExpression(val) => return val.is_dereference(),
+            _ => return false,
+        }
+    }
+
+    // whether the node is a value, rather than a statement
+    pub fn is_expression(&self) -> bool{
+        match self{
+            Node::ValueInt(_)           | Node::ValueUInt(_)         | Node::ValueFloat(_)  |
+            Node::ValueString(_)        | Node::InterpolatedString(_) | Node::VariableCall(_) |
+            Node::BinaryExpression(_)   | Node::UnaryExpression(_)    | Node::FunctionCall(_) |
+            Node::Lambda(_)             | Node::ArrayLiteral(_)       | Node::Index(_)        |
+            Node::FieldAccess(_)        | Node::TupleLiteral(_)       | Node::Wrapper(_)     |
+            Node::Try(_)                | Node::Alloc(_) => return true,
+            _ => return false,
+        }
+    }
+
+    // the value of an analyzed expression, if it can be computed at compile time
+    pub fn fold(&self) -> Option<ConstValue>{
+        match self{
+            Node::ValueInt(val)        => return Some(ConstValue::Int(val.value as i128)),
+            Node::ValueUInt(val)       => return Some(ConstValue::Int(val.value as i128)),
+            Node::ValueFloat(val)      => return Some(ConstValue::Float(val.value)),
+            Node::ValueString(val)     => return Some(ConstValue::Str(val.to_c())),
+            Node::VariableCall(val)    => return val.fold(),
+            Node::UnaryExpression(val) => return val.fold(),
+            Node::BinaryExpression(val) => return val.fold(),
+            _ => return None,
+        }
+    }
+
+    // the array literals take the type of whatever they are assigned to (as long as their elements
+    // fit), null the function type it is used as, "some(x)" or "none" the option they are
+    // assigned to and alloc() the pointer, every other node keeps the type it was analyzed with
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        match self{
+            Node::ArrayLiteral(val) => return val.coerce(value_type, target),
+            Node::TupleLiteral(val) => return val.coerce(value_type, target),
+            Node::VariableCall(val) => return val.coerce(value_type, target),
+            Node::Wrapper(val)      => return val.coerce(value_type, target),
+            Node::Alloc(val)        => return val.coerce(value_type, target),
+            _ => return value_type,
+        }
+    }
 }
 
 impl From<&Token> for Node{
@@ -84,32 +239,107 @@ impl From<&Token> for Node{
             Token::UInt64(val)  => return Node::ValueUInt(NodeValueUInt::new(*val as u64, VarType::U64)),
             Token::Float32(val) => return Node::ValueFloat(NodeValueFloat::new(*val as f64, VarType::F32)),
             Token::Float64(val) => return Node::ValueFloat(NodeValueFloat::new(*val as f64, VarType::F64)),
+            Token::String(val) if is_interpolated(val) => return Node::InterpolatedString(NodeInterpolatedString::new(val)),
             Token::String(val)  => return Node::ValueString(NodeValueString::new(val.to_string(), VarType::Str)),
             Token::Identifier(_val) => return Node::VariableCall(NodeVariableCall::from(token)),
+            Token::Keyword(Keyword::Return) => return Node::Return(NodeReturn::new(None)),
+            Token::Keyword(Keyword::None) => return Node::Wrapper(NodeWrapper::new("none", None)),
+            Token::Keyword(Keyword::Break) => return Node::Jump(NodeJump::new(Keyword::Break)),
+            Token::Keyword(Keyword::Continue) => return Node::Jump(NodeJump::new(Keyword::Continue)),
             _ => todo!(),
         }
     }
 }
 
-// returns the index of the closing right parenthesis of the given statement
-fn get_closing_rpar(tokens: &Vec<Token>) -> usize{
+// the statements of a block, followed by the ones deferred in it (unless the block ends with
+// a return, a break or a continue, which run them on their own)
+fn body_to_c(body: &Vec<Box<Node>>) -> String{
+    let mut result: String = String::new();
+    for node in body{
+        if matches!(**node, Node::Defer(_)) {continue;}
+        result.push_str(&node.to_c());
+        result.push_str(";\n");
+    }
+    if matches!(body.last().map(|node| &**node), Some(Node::Return(_)) | Some(Node::Jump(_))) {return result;}
+    for node in body.iter().rev(){
+        if let Node::Defer(defer) = &**node{
+            result.push_str(&defer.statement.to_c());
+            result.push_str(";\n");
+        }
+    }
+    return result;
+}
+
+// the statements deferred in the scopes a return or a break leaves, as the table gave them
+fn cleanup_to_c(statements: &Vec<Node>) -> String{
+    let mut result: String = String::new();
+    for statement in statements{
+        result.push_str(&statement.to_c());
+        result.push_str(";\n");
+    }
+    return result;
+}
+
+// returns the index of the parenthesis or bracket closing the one the tokens start with
+fn get_closing(tokens: &[Token]) -> usize{
     let mut i = 0;
     let mut openings = 0;
     loop{
         match tokens[i] {
-            Token::LPar => openings += 1,
-            Token::RPar => openings -= 1,
+            Token::LPar | Token::LBracket => openings += 1,
+            Token::RPar | Token::RBracket => openings -= 1,
             _ => (),
         }
-        if openings == 0 && tokens[i] == Token::RPar {break;}
+        if openings == 0 {break;}
         i += 1;
     }
     return i;
 }
 
+// splits the tokens of an argument list on the commas which are not nested in parentheses
+fn split_arguments(tokens: &[Token]) -> Vec<Vec<Token>>{
+    let mut result: Vec<Vec<Token>> = Vec::new();
+    let mut current: Vec<Token> = Vec::new();
+    let mut openings = 0;
+    for token in tokens{
+        match token{
+            Token::LPar | Token::LBracket => openings += 1,
+            Token::RPar | Token::RBracket => openings -= 1,
+            Token::Comma if openings == 0 => {
+                result.push(current);
+                current = Vec::new();
+                continue;
+            },
+            _ => (),
+        }
+        current.push(token.clone());
+    }
+    if !current.is_empty() {result.push(current);}
+    return result;
+}
+
+// "Name variable" or "module.Name variable"
+fn starts_with_struct_type(tokens: &Vec<Token>) -> bool{
+    let mut i = 1;
+    while i + 1 < tokens.len() && tokens[i] == Token::Dot && matches!(tokens[i + 1], Token::Identifier(_)) {i += 2;}
+    return matches!(tokens.get(i), Some(Token::Identifier(_)));
+}
+
+// "*i32 variable" - a dereference is never followed by a type
+fn starts_with_pointer_type(tokens: &Vec<Token>) -> bool{
+    let mut i = 0;
+    while matches!(tokens.get(i), Some(Token::Mul) | Some(Token::Exp)) {i += 1;}
+    match tokens.get(i){
+        Some(Token::Keyword(keyword)) => return VarType::from(*keyword) != VarType::None || *keyword == Keyword::List || *keyword == Keyword::None,
+        Some(Token::Identifier(_val)) if is_sum_type(tokens, i) => return true,
+        Some(Token::Identifier(_val)) => return starts_with_struct_type(&tokens[i..].to_vec()),
+        Some(Token::LBracket) => return matches!(tokens.get(i + get_closing(&tokens[i..]) + 1), Some(Token::Identifier(_))),
+        _ => return false,
+    }
+}
+
 impl From<Vec<Token>> for Node{
     fn from(tokens: Vec<Token>) -> Node{
-        println!("from tokens = {:#?}\n", tokens);
         if tokens.len() == 1 {return Node::from(&tokens[0]);}
         match &tokens[0]{
             Token::Keyword(Keyword::Auto)  => return generate_variable(&tokens),
@@ -124,16 +354,36 @@ impl From<Vec<Token>> for Node{
             Token::Keyword(Keyword::F32)   => return generate_variable(&tokens),
             Token::Keyword(Keyword::F64)   => return generate_variable(&tokens),
             Token::Keyword(Keyword::Str)   => return generate_variable(&tokens),
+            Token::Keyword(Keyword::Bool)  => return generate_variable(&tokens),
+            Token::Keyword(Keyword::List)  => return generate_variable(&tokens),
+            // "option[i32] x" - the brackets would be an index otherwise
+            Token::Identifier(_val) if is_sum_type(&tokens, 0) && matches!(tokens.get(get_closing(&tokens[1..]) + 2), Some(Token::Identifier(_))) => return generate_variable(&tokens),
+            // a struct type is followed by the name of the variable, a value by an operator
+            Token::Identifier(_val) if starts_with_struct_type(&tokens) => return generate_variable(&tokens),
+            // an array type is followed by the name of the variable, an array literal never is
+            Token::LBracket if matches!(tokens.get(get_closing(&tokens) + 1), Some(Token::Identifier(_))) => return generate_variable(&tokens),
+            // a tuple type is followed by the name of the variable too
+            Token::LPar if matches!(tokens.get(get_closing(&tokens) + 1), Some(Token::Identifier(_))) => return generate_variable(&tokens),
+            Token::Mul | Token::Exp if starts_with_pointer_type(&tokens) => return generate_variable(&tokens),
+            // a function type is followed by the name of the variable, a function definition starts with its name
+            Token::Keyword(Keyword::Fn) if tokens.get(1) == Some(&Token::LPar) => return generate_variable(&tokens),
             Token::Keyword(Keyword::Fn)    => return generate_function(&tokens), 
-            Token::Not                     => return Node::UnaryExpression(NodeUnaryExpression::from(&tokens)),
+            Token::Keyword(Keyword::Extern) if tokens[1] == Token::Keyword(Keyword::Fn) => return generate_extern(&tokens),
+            Token::Keyword(Keyword::Extern) => return Node::Include(NodeInclude::from(&tokens)),
+            Token::Keyword(Keyword::Export) => return generate_export(&tokens),
+            Token::Keyword(Keyword::Struct) => return Node::StructDefinition(NodeStructDefinition::from(&tokens)),
+            Token::Keyword(Keyword::Enum)  => return Node::EnumDefinition(NodeEnumDefinition::from(&tokens)),
+            Token::Keyword(Keyword::Match) => return Node::Match(NodeMatch::from(&tokens)),
+            Token::Keyword(Keyword::Defer) => return Node::Defer(NodeDefer::from(&tokens)),
             Token::Keyword(Keyword::If)    => return Node::IfStatement(NodeIfStatement::from(&tokens)),
             Token::Keyword(Keyword::While) => return Node::WhileLoop(NodeWhileLoop::from(&tokens)),
-            Token::Identifier(_val) => return match tokens[1]{
-                Token::LPar => return Node::FunctionCall(NodeFunctionCall::new(&tokens)),
-                _           => return Node::BinaryExpression(generate_binary_expression_tree(&tokens)),
-            },
-            Token::LPar => return Node::from(tokens[1 .. get_closing_rpar(&tokens)].to_vec()),
-            _ => todo!()
+            Token::Keyword(Keyword::Return) => return Node::Return(NodeReturn::from(&tokens)),
+            Token::Keyword(Keyword::For)   => return Node::ForLoop(NodeForLoop::from(&tokens)),
+            Token::Keyword(Keyword::Const) => return Node::Global(NodeGlobal::from(&tokens)),
+            Token::Keyword(Keyword::Import) => return Node::Import(NodeImport::from(&tokens)),
+            Token::Keyword(Keyword::Use)   => return Node::Import(NodeImport::from(&tokens)),
+            Token::Identifier(_val) if opens_block(&tokens, 0) => return Node::InlineC(NodeInlineC::from(&tokens)),
+            _ => return generate_expression(&tokens),
         }
     }
 }
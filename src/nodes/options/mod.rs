@@ -0,0 +1,138 @@
+use crate::parser::*;
+use crate::semantic::*;
+use super::{Node, cleanup_to_c};
+
+// "some(x)", "none", "ok(x)" and "err(e)" - the constructors of the options and the results, which
+// only know a part of their type until they are assigned somewhere
+#[derive(Debug, Clone)]
+pub struct NodeWrapper{
+    kind: String,
+    value: Option<Box<Node>>,
+    // set by the semantic pass
+    value_type: VarType,
+    var_type: VarType,
+}
+
+impl NodeWrapper{
+    pub fn new(kind: &str, value: Option<Box<Node>>) -> Self{
+        NodeWrapper {kind: kind.to_string(), value: value, value_type: VarType::None, var_type: VarType::None}
+    }
+
+    // "((__option_i32){.is_some = 1, .value = x})"
+    pub fn to_c(&self) -> String{
+        let name: String = self.var_type.to_c().trim().to_string();
+        let value: String = match &self.value{
+            Some(value) => value.to_c(),
+            None => String::new(),
+        };
+        match self.kind.as_str(){
+            "some" => return format!("(({}){{.is_some = 1, .value = {}}})", name, value),
+            "ok" if self.value.is_none() => return format!("(({}){{.is_ok = 1}})", name),
+            "ok" => return format!("(({}){{.is_ok = 1, .value = {}}})", name, value),
+            "err" => return format!("(({}){{.is_ok = 0, .error = {}}})", name, value),
+            _ => return format!("(({}){{0}})", name),
+        }
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.value_type = match self.value.as_mut(){
+            Some(value) => value.analyze(table)?,
+            None => VarType::None,
+        };
+        if self.value_type == VarType::None && (self.kind == "some" || self.kind == "err"){
+            return Err(format!("'{}' needs a value", self.kind));
+        }
+        let value_type = Box::new(self.value_type.clone());
+        self.var_type = match self.kind.as_str(){
+            "some" => VarType::Option(value_type),
+            "ok"   => VarType::Result(value_type, Box::new(VarType::Auto)),
+            "err"  => VarType::Result(Box::new(VarType::Auto), value_type),
+            _      => VarType::Option(Box::new(VarType::Auto)),
+        };
+        return Ok(self.var_type.clone());
+    }
+
+    // the constructor becomes the option or the result it is used as, as long as its value fits
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        let expected: &VarType = match (self.kind.as_str(), target){
+            ("none", VarType::Option(_)) => {
+                self.var_type = target.clone();
+                return self.var_type.clone();
+            },
+            ("some", VarType::Option(value)) => value,
+            ("ok", VarType::Result(value, _error)) => value,
+            ("err", VarType::Result(_value, error)) => error,
+            _ => return value_type,
+        };
+        let coerced: VarType = match self.value.as_mut(){
+            Some(value) => value.coerce(self.value_type.clone(), expected),
+            None => VarType::None,
+        };
+        if coerced != *expected && (coerced == VarType::None || !is_compatible(&coerced, expected)) {return value_type;}
+        self.value_type = coerced;
+        self.var_type = target.clone();
+        return self.var_type.clone();
+    }
+}
+
+// "value?" - the value of an option or of a result, the none or the error is returned from the
+// function right away (a GCC statement expression, as a return can't be a part of a C expression)
+#[derive(Debug, Clone)]
+pub struct NodeTry{
+    value: Box<Node>,
+    // set by the semantic pass
+    value_type: VarType,
+    return_type: VarType,
+    cleanup: Vec<Node>, // the deferred statements, run before returning
+}
+
+impl NodeTry{
+    pub fn new(value: Node) -> Self{
+        NodeTry {value: Box::new(value), value_type: VarType::None, return_type: VarType::None, cleanup: Vec::new()}
+    }
+
+    pub fn to_c(&self) -> String{
+        let mut result: String = "({".to_owned();
+        result.push_str(&self.value_type.to_c());
+        result.push_str("__try = ");
+        result.push_str(&self.value.to_c());
+        result.push_str("; ");
+        let return_type: String = self.return_type.to_c().trim().to_string();
+        let cleanup: String = cleanup_to_c(&self.cleanup);
+        match &self.value_type{
+            VarType::Option(_value) => {
+                result.push_str(&format!("if(!__try.is_some){{ {}return (({}){{0}}); }} __try.value; ", cleanup, return_type));
+            },
+            VarType::Result(value, _error) => {
+                result.push_str(&format!("if(!__try.is_ok){{ {}return (({}){{.is_ok = 0, .error = __try.error}}); }} ", cleanup, return_type));
+                if **value != VarType::None {result.push_str("__try.value; ");}
+            },
+            _ => (),
+        }
+        result.push_str("})");
+        return result;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.value_type = self.value.analyze(table)?;
+        self.return_type = match &table.return_type{
+            Some(return_type) => return_type.clone(),
+            None => return Err("'?' outside of a function".to_string()),
+        };
+        self.cleanup = table.deferred(table.function_scope());
+        if is_partial(&self.value_type){
+            return Err(format!("'?' cannot be used on a value of type {}", self.value_type));
+        }
+        match (&self.value_type, &self.return_type){
+            (VarType::Option(value), VarType::Option(_)) => return Ok(*value.clone()),
+            (VarType::Option(_), return_type) => {
+                return Err(format!("'?' on an option needs the function to return an option, not {}", return_type));
+            },
+            (VarType::Result(value, error), VarType::Result(_, return_error)) if is_compatible(error, return_error) => return Ok(*value.clone()),
+            (VarType::Result(_, error), return_type) => {
+                return Err(format!("'?' on a result with an error of type {} cannot return it from a function returning {}", error, return_type));
+            },
+            (value_type, _) => return Err(format!("'?' needs an option or a result, not a value of type {}", value_type)),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+use super::binary_expression::try_generate_expression;
+
+// the part after the ':' in "{value:>8.2}" - modeled after printf, so the
+// supported alignments are only '<' (left) and '>' (right, the default)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatSpec{
+    align_left: bool,
+    sign: bool,
+    zero_pad: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+impl FormatSpec{
+    pub fn new() -> Self{
+        FormatSpec {align_left: false, sign: false, zero_pad: false, width: None, precision: None}
+    }
+
+    fn take_number(chars: &Vec<char>, i: &mut usize) -> Option<usize>{
+        let start = *i;
+        while *i < chars.len() && chars[*i].is_ascii_digit() {*i += 1;}
+        if start == *i {return None;}
+        return chars[start .. *i].iter().collect::<String>().parse().ok();
+    }
+
+    pub fn parse(spec: &str) -> Result<FormatSpec, String>{
+        let chars: Vec<char> = spec.chars().collect();
+        let mut result = FormatSpec::new();
+        let mut i = 0;
+
+        if i < chars.len() {
+            match chars[i]{
+                '<' => {result.align_left = true; i += 1;},
+                '>' => i += 1,
+                '^' => return Err(format!("center alignment is not supported in format spec '{}'", spec)),
+                _ => (),
+            }
+        }
+        if i < chars.len() && chars[i] == '+' {result.sign = true; i += 1;}
+        if i < chars.len() && chars[i] == '0' {result.zero_pad = true; i += 1;}
+        result.width = FormatSpec::take_number(&chars, &mut i);
+        if i < chars.len() && chars[i] == '.' {
+            i += 1;
+            result.precision = FormatSpec::take_number(&chars, &mut i);
+            if result.precision.is_none() {return Err(format!("missing precision in format spec '{}'", spec));}
+        }
+        if i != chars.len() {return Err(format!("invalid format spec '{}'", spec));}
+        return Ok(result);
+    }
+
+    // builds the printf conversion for a value of the given type, e.g. "%-8.2lf"
+    pub fn to_c_printf(&self, var_type: &VarType) -> Result<String, String>{
+        if !is_numeric(var_type) && (self.sign || self.zero_pad){
+            return Err("the '+' and '0' format flags are only allowed for numbers".to_string());
+        }
+        if ((is_numeric(var_type) && !is_float(var_type)) || *var_type == VarType::Bool) && self.precision.is_some(){
+            return Err(format!("a precision is not allowed for values of type {}", var_type));
+        }
+
+        let conversion = var_type.to_c_printf();
+        let mut result: String = "%".to_owned();
+        if self.align_left {result.push('-');}
+        if self.sign {result.push('+');}
+        if self.zero_pad {result.push('0');}
+        if let Some(width) = self.width {result.push_str(&width.to_string());}
+        if let Some(precision) = self.precision {
+            result.push('.');
+            result.push_str(&precision.to_string());
+        }
+        result.push_str(&conversion[1..]);
+        return Ok(result);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InterpolationPart{
+    Text(String),
+    Value{
+        value: Box<Node>,
+        spec: String,
+        // filled in by the semantic pass
+        var_type: VarType,
+        conversion: String,
+    },
+}
+
+// the literal is only split into its parts by the semantic pass, so the mistakes in it are
+// reported like the other errors
+#[derive(Debug, Clone)]
+pub struct NodeInterpolatedString{
+    literal: String,
+    parts: Vec<InterpolationPart>,
+}
+
+// whether the string literal (with its quotes) contains any "{...}" or escaped braces
+pub fn is_interpolated(literal: &String) -> bool{
+    let chars: Vec<char> = literal.chars().collect();
+    let mut i = 0;
+    while i < chars.len(){
+        match chars[i]{
+            '\\' if i + 1 < chars.len() && (chars[i + 1] == '{' || chars[i + 1] == '}') => return true,
+            '\\' => i += 1,
+            '{' => return true,
+            _ => (),
+        }
+        i += 1;
+    }
+    return false;
+}
+
+// the interpolated values are lexed and parsed the same way as any other expression
+// (the quotes of the nested string literals are escaped as '\"')
+fn parse_value(source: &str) -> Result<Box<Node>, String>{
+    // the braces are inside a string, so their quotes and backslashes are escaped once more
+    let mut unescaped: String = String::new();
+    let mut chars = source.chars();
+    while let Some(c) = chars.next(){
+        match c{
+            '\\' => match chars.next(){
+                Some(next) if next == '"' || next == '\\' => unescaped.push(next),
+                Some(next) => {unescaped.push(c); unescaped.push(next);},
+                None => unescaped.push(c),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    let tokens: Vec<Token> = lexer(&unescaped)
+        .into_iter()
+        .filter(|token| *token != Token::NewLine)
+        .collect();
+    if tokens.is_empty() {return Err("empty '{}' in string literal".to_string());}
+    let mut depth: i32 = 0;
+    for token in &tokens{
+        match token{
+            Token::LPar | Token::LBracket => depth += 1,
+            Token::RPar | Token::RBracket => depth -= 1,
+            _ => (),
+        }
+        if depth < 0 {break;}
+    }
+    if depth != 0 {return Err(format!("unbalanced brackets in '{{{}}}'", source));}
+    let value = try_generate_expression(&tokens).map_err(|error| format!("{} in '{{{}}}'", error, source))?;
+    return Ok(Box::new(value));
+}
+
+impl NodeInterpolatedString{
+    pub fn new(literal: &String) -> Self{
+        NodeInterpolatedString {literal: literal.to_string(), parts: Vec::new()}
+    }
+
+    // the format string shared by printf and the runtime formatting
+    fn to_c_format(&self) -> String{
+        let mut result: String = "\"".to_owned();
+        for part in &self.parts{
+            match part{
+                InterpolationPart::Text(text) => result.push_str(&text.replace('%', "%%")),
+                InterpolationPart::Value {conversion, ..} => result.push_str(conversion),
+            }
+        }
+        result.push('"');
+        return result;
+    }
+
+    // the arguments of a printf call printing this string
+    pub fn to_c_printf_args(&self) -> String{
+        let mut result: String = self.to_c_format();
+        for part in &self.parts{
+            if let InterpolationPart::Value {value, var_type: VarType::Bool, ..} = part{
+                result.push_str(&format!(", (({}) ? \"true\" : \"false\")", value.to_c()));
+            }else if let InterpolationPart::Value {value, var_type, ..} = part{
+                // the cast makes sure the value matches the conversion after the C promotions
+                result.push_str(", (");
+                result.push_str(var_type.to_c().trim());
+                result.push_str(")(");
+                result.push_str(&value.to_c());
+                result.push(')');
+            }
+        }
+        return result;
+    }
+
+    pub fn to_c(&self) -> String{
+        let mut result: String = "__chal_format(".to_owned();
+        result.push_str(&self.to_c_printf_args());
+        result.push(')');
+        return result;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.parts = NodeInterpolatedString::from(&self.literal)?.parts;
+        for part in self.parts.iter_mut(){
+            if let InterpolationPart::Value {value, spec, var_type, conversion} = part{
+                let value_type = value.analyze(table)?;
+                if value_type != VarType::Str && !is_condition(&value_type){
+                    return Err(format!("cannot interpolate a value of type {}", value_type));
+                }
+                *conversion = FormatSpec::parse(spec)?.to_c_printf(&value_type)?;
+                *var_type = value_type;
+            }
+        }
+        return Ok(VarType::Str);
+    }
+}
+
+impl NodeInterpolatedString{
+    pub fn from(literal: &String) -> Result<Self, String>{
+        let chars: Vec<char> = literal[1 .. literal.len() - 1].chars().collect();
+        let mut result = NodeInterpolatedString::new(literal);
+        let mut text: String = String::new();
+        let mut i = 0;
+        while i < chars.len(){
+            match chars[i]{
+                // '\{' and '\}' are the escaped braces, every other escape is left for C
+                '\\' if i + 1 < chars.len() && (chars[i + 1] == '{' || chars[i + 1] == '}') => {
+                    text.push(chars[i + 1]);
+                    i += 1;
+                },
+                '\\' if i + 1 < chars.len() => {
+                    text.push(chars[i]);
+                    text.push(chars[i + 1]);
+                    i += 1;
+                },
+                '{' => {
+                    let closing = match find_outside_strings(&chars, i + 1, '}'){
+                        Some(position) => position,
+                        None => return Err("unterminated '{' in string literal".to_string()),
+                    };
+                    let inside: Vec<char> = chars[i + 1 .. closing].to_vec();
+                    let (source, spec): (String, String) = match find_outside_strings(&inside, 0, ':'){
+                        Some(position) => (inside[..position].iter().collect(), inside[position + 1..].iter().collect()),
+                        None => (inside.iter().collect(), String::new()),
+                    };
+                    let (source, spec) = (source.as_str(), spec.as_str());
+                    if !text.is_empty() {result.parts.push(InterpolationPart::Text(text));}
+                    text = String::new();
+                    result.parts.push(InterpolationPart::Value {
+                        value: parse_value(source)?,
+                        spec: spec.to_string(),
+                        var_type: VarType::None,
+                        conversion: String::new(),
+                    });
+                    i = closing;
+                },
+                '}' => return Err("unmatched '}' in string literal".to_string()),
+                c => text.push(c),
+            }
+            i += 1;
+        }
+        if !text.is_empty() {result.parts.push(InterpolationPart::Text(text));}
+        return Ok(result);
+    }
+}
+
+// the position of the first 'target' from 'start' on, past the strings written in the braces
+// with escaped quotes - "{concat(\"}:\", name)}"
+fn find_outside_strings(chars: &[char], start: usize, target: char) -> Option<usize>{
+    let mut in_string: bool = false;
+    let mut i = start;
+    while i < chars.len(){
+        match chars[i]{
+            // a quote in a nested string is escaped twice - "\\\""
+            '\\' if in_string && chars[i + 1..].starts_with(&['\\', '\\', '"']) => i += 3,
+            '\\' if i + 1 < chars.len() && chars[i + 1] == '"' => {
+                in_string = !in_string;
+                i += 1;
+            },
+            '\\' if in_string && i + 1 < chars.len() => i += 1,
+            c if c == target && !in_string => return Some(i),
+            _ => (),
+        }
+        i += 1;
+    }
+    return None;
+}
@@ -0,0 +1,99 @@
+use crate::lexer::*;
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+
+// checks the fields of a struct (or of an enum variant) and resolves their types
+pub fn resolve_fields(table: &SymbolTable, owner: &String, fields: &StructFields) -> Result<StructFields, String>{
+    let mut result: StructFields = Vec::new();
+    for (field, field_type) in fields{
+        if result.iter().any(|(name, _field_type)| name == field){
+            return Err(format!("{} has more than one field '{}'", owner, field));
+        }
+        let field_type = table.resolve_type(field_type)?;
+        if field_type == VarType::None || field_type == VarType::Auto{
+            return Err(format!("the field '{}' of {} cannot be of type {}", field, owner, field_type));
+        }
+        result.push((field.to_string(), field_type));
+    }
+    return Ok(result);
+}
+
+// struct Name:
+//     field: type
+// end
+#[derive(Debug, Clone)]
+pub struct NodeStructDefinition{
+    name: String,
+    full_name: String, // set by the semantic pass
+    fields: StructFields,
+}
+
+impl NodeStructDefinition{
+    // the C struct is declared with the other types, before any of the code
+    pub fn to_c(&self) -> String{
+        "".to_string()
+    }
+
+    pub fn declare(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        self.full_name = table.declare_struct(&self.name)?;
+        return Ok(());
+    }
+
+    pub fn define(&mut self, table: &mut SymbolTable) -> Result<(), String>{
+        let fields = resolve_fields(table, &format!("struct '{}'", self.name), &self.fields)?;
+        table.define_struct(&self.full_name, fields);
+        return Ok(());
+    }
+
+    // everything is done before the rest of the module is analyzed
+    pub fn analyze(&mut self, _table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.full_name.is_empty(){
+            return Err("structs can only be defined at the top level of a module".to_string());
+        }
+        return Ok(VarType::None);
+    }
+}
+
+impl From<&Vec<Token>> for NodeStructDefinition{
+    fn from(tokens: &Vec<Token>) -> Self{
+        let mut result = NodeStructDefinition {name: String::new(), full_name: String::new(), fields: Vec::new()};
+        let mut i = 1;
+        result.name = get_token_value!(&tokens[i], Token::Identifier).expect("expected the name of the struct").to_string();
+        i += 1;
+        if tokens[i] != Token::Colon {panic!("expected ':' after the name of struct '{}'", result.name);}
+        i += 1;
+
+        while i < tokens.len() && tokens[i] != Token::Keyword(Keyword::End){
+            if tokens[i] == Token::NewLine {i += 1; continue;}
+            let field = get_token_value!(&tokens[i], Token::Identifier).expect("expected the name of a field").to_string();
+            i += 1;
+            if tokens[i] != Token::Colon {panic!("expected ':' after the field '{}'", field);}
+            i += 1;
+            result.fields.push((field, parse_type(tokens, &mut i)));
+        }
+        return result;
+    }
+}
+
+// a field of any value which is not just a variable - "points[0].x"
+#[derive(Debug, Clone)]
+pub struct NodeFieldAccess{
+    pub object: Box<Node>,
+    field: String,
+}
+
+impl NodeFieldAccess{
+    pub fn new(object: Node, field: String) -> Self{
+        NodeFieldAccess {object: Box::new(object), field: field}
+    }
+
+    pub fn to_c(&self) -> String{
+        return format!("({}).{}", self.object.to_c(), self.field);
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let object_type = self.object.analyze(table)?;
+        return table.get_field(&object_type, &self.field);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::parser::*;
+use crate::semantic::*;
+use super::Node;
+
+// "(q, r == 0)" - a C struct with a field for every element, "_0", "_1" and so on
+#[derive(Debug, Clone)]
+pub struct NodeTupleLiteral{
+    elements: Vec<Box<Node>>,
+    // set by the semantic pass
+    element_types: Vec<VarType>,
+    var_type: VarType,
+}
+
+impl NodeTupleLiteral{
+    pub fn new(elements: Vec<Box<Node>>) -> Self{
+        NodeTupleLiteral {elements: elements, element_types: Vec::new(), var_type: VarType::None}
+    }
+
+    pub fn to_c(&self) -> String{
+        let mut elements: String = String::new();
+        for i in 0..self.elements.len(){
+            elements.push_str(&self.elements[i].to_c());
+            if i != self.elements.len() - 1 {elements.push_str(", ");}
+        }
+        return format!("(({}){{{}}})", self.var_type.to_c().trim(), elements);
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        self.element_types = Vec::new();
+        for element in self.elements.iter_mut(){
+            let element_type = element.analyze(table)?;
+            if element_type == VarType::None{
+                return Err("the elements of a tuple cannot be of type none".to_string());
+            }
+            self.element_types.push(element_type);
+        }
+        self.var_type = VarType::Tuple(self.element_types.clone());
+        return Ok(self.var_type.clone());
+    }
+
+    // the literal becomes a tuple of the same length, as long as all of its elements can be
+    // converted to the types of the elements of that tuple
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        let targets = match target{
+            VarType::Tuple(targets) if targets.len() == self.elements.len() => targets,
+            _ => return value_type,
+        };
+        let mut element_types: Vec<VarType> = Vec::new();
+        for i in 0..self.elements.len(){
+            element_types.push(self.elements[i].coerce(self.element_types[i].clone(), &targets[i]));
+        }
+        if (0..targets.len()).any(|i| !is_compatible(&element_types[i], &targets[i])) {return value_type;}
+        self.element_types = element_types;
+        self.var_type = target.clone();
+        return self.var_type.clone();
+    }
+}
@@ -1,28 +1,71 @@
 use crate::lexer::*;
 use crate::parser::*;
+use crate::semantic::*;
 use super::Node;
+use super::globals::ConstValue;
 
 #[derive(Debug, Clone)]
 pub struct NodeUnaryExpression{
     operand: Box<Node>,
     operator: OperatorType,
+    operand_type: VarType, // set by the semantic pass
 }
 
 impl NodeUnaryExpression{
-    fn new() -> Self{
+    pub fn new(operand: Node, operator: OperatorType) -> Self{
         NodeUnaryExpression{
-            operand: Box::new(Node::new()),
-            operator: OperatorType::new(),
+            operand: Box::new(operand),
+            operator: operator,
+            operand_type: VarType::None,
         }
     }
 
+    pub fn is_dereference(&self) -> bool{
+        return matches!(self.operator, OperatorType::Dereference);
+    }
+
+    pub fn fold(&self) -> Option<ConstValue>{
+        return ConstValue::unary(&self.operator, self.operand.fold()?);
+    }
+
     pub fn to_c(&self) -> String{
+        // the null pointers are caught before they are dereferenced (except in the release builds)
+        if self.is_dereference(){
+            return format!("(*({})__CHAL_CHECK_NULL({}))", self.operand_type.to_c(), self.operand.to_c());
+        }
         let mut result: String = self.operator.to_c().to_owned();
         result.push_str("(");
         result.push_str(&self.operand.to_c().to_owned());
         result.push_str(")");
         return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        let operand_type = self.operand.analyze(table)?;
+        self.operand_type = operand_type.clone();
+        match self.operator{
+            OperatorType::Reference => {
+                if !self.operand.is_lvalue(){
+                    return Err("cannot take the address of a value which is not stored in a variable".to_string());
+                }
+                return Ok(VarType::Pointer(Box::new(operand_type)));
+            },
+            OperatorType::Dereference => match operand_type{
+                VarType::Pointer(target) if *target != VarType::None => return Ok(*target),
+                _ => return Err(format!("cannot dereference a value of type {}", operand_type)),
+            },
+            _ => (),
+        }
+        let negation: bool = matches!(self.operator, OperatorType::Not) && operand_type == VarType::Bool;
+        if !is_numeric(&operand_type) && !negation{
+            return Err(format!("cannot apply '{}' to a value of type {}", self.operator.to_c().trim(), operand_type));
+        }
+        if matches!(self.operator, OperatorType::Minus) && is_unsigned(&operand_type){
+            return Err(format!("cannot negate a value of the unsigned type {}", operand_type));
+        }
+        if self.operator.is_logical() {return Ok(VarType::Bool);}
+        return Ok(operand_type);
+    }
 }
 
 impl From<&Vec<Token>> for NodeUnaryExpression{
@@ -30,6 +73,7 @@ impl From<&Vec<Token>> for NodeUnaryExpression{
         NodeUnaryExpression{
             operand: Box::new(Node::from(tokens[1..].to_vec())),
             operator: OperatorType::from(&tokens[0]),
+            operand_type: VarType::None,
         }
     }
 }
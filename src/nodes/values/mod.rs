@@ -2,7 +2,7 @@ use crate::parser::VarType;
 
 #[derive(Debug, Clone)]
 pub struct NodeValueInt{
-        value: i64,
+        pub value: i64,
         pub var_type: VarType,
 }
 
@@ -18,7 +18,7 @@ impl NodeValueInt{
 
 #[derive(Debug, Clone)]
 pub struct NodeValueUInt{
-    value: u64,
+    pub value: u64,
     pub var_type: VarType,
 }
 
@@ -34,7 +34,7 @@ impl NodeValueUInt{
 
 #[derive(Debug, Clone)]
 pub struct NodeValueFloat{
-    value: f64,
+    pub value: f64,
     pub var_type: VarType,
 }
 
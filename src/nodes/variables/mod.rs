@@ -1,29 +1,152 @@
 use crate::lexer::*;
 use crate::parser::*;
+use crate::semantic::*;
+use crate::builtins;
 use super::Node;
+use super::enums::variant_to_c;
+use super::globals::ConstValue;
+use super::functions::function_value_to_c;
 
-use crate::nodes::{generate_binary_expression_tree, evaluate_var_type};
+use crate::nodes::generate_expression;
+use super::{get_closing, split_arguments};
 
 #[derive(Debug, Clone)]
 pub struct NodeVariableCall{
     name: String,
+    // set by the semantic pass - the value of a constant (an enum variant too) or the C name of a global
+    pub constant: Option<ConstValue>,
+    global: Option<String>,
+    function: Option<FunctionSignature>, // the function this names, when it is used as a value
 }
 
 impl NodeVariableCall{
-    pub fn new() -> Self{
+    pub fn new(name: String) -> Self{
         NodeVariableCall{
-            name: "".to_owned()
+            name: name,
+            constant: None,
+            global: None,
+            function: None,
         } 
     }
 
     pub fn to_c(&self) -> String{
+        if let Some(value) = self.fold() {return value.to_c();}
+        if let Some(global) = &self.global {return global.to_owned();}
         self.name.to_owned()
     }
+
+    pub fn name(&self) -> &String{
+        return &self.name;
+    }
+
+    pub fn is_assignable(&self) -> bool{
+        return self.constant.is_none() && self.function.is_none();
+    }
+
+    pub fn is_function(&self) -> bool{
+        return self.function.is_some();
+    }
+
+    // the wrapper of a function is only made once the function is really used as a value
+    pub fn fold(&self) -> Option<ConstValue>{
+        if let Some(signature) = &self.function {return Some(ConstValue::Symbol(function_value_to_c(signature)));}
+        return self.constant.clone();
+    }
+
+    // "name", "name.field" or "module.name.field" - the longest name which is a global wins
+    fn analyze_global(&mut self, table: &SymbolTable) -> Option<Result<VarType, String>>{
+        let parts: Vec<&str> = self.name.split('.').collect();
+        for length in (1..=parts.len()).rev(){
+            let global = match table.get_global(&parts[..length].join(".")){
+                Some(global) => global,
+                None => continue,
+            };
+            if length == parts.len() && global.value.is_some(){
+                self.constant = global.value;
+                return Some(Ok(global.var_type));
+            }
+            let mut var_type = global.var_type;
+            for field in &parts[length..]{
+                var_type = match table.get_field(&var_type, field){
+                    Ok(field_type) => field_type,
+                    Err(error) => return Some(Err(error)),
+                };
+            }
+            let mut c_name: String = global.c_name;
+            for field in &parts[length..]{
+                c_name = format!("{}.{}", c_name, field);
+            }
+            self.global = Some(c_name);
+            return Some(Ok(var_type));
+        }
+        return None;
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        // "point.x" is a field of a variable, the same way "module.name" is a name from a module
+        if let Some((variable, fields)) = self.name.split_once('.'){
+            if let Ok(mut var_type) = table.get_variable(&variable.to_string()){
+                if table.is_captured(&variable.to_string()) {self.global = Some(format!("__env->{}", self.name));}
+                for field in fields.split('.'){
+                    var_type = table.get_field(&var_type, field)?;
+                }
+                return Ok(var_type);
+            }
+        }
+
+        let variable = table.get_variable(&self.name);
+        // the lambdas keep the variables they capture in their environment
+        if variable.is_ok() && table.is_captured(&self.name){
+            self.global = Some(format!("__env->{}", self.name));
+        }
+        if variable.is_err(){
+            if let Some(global) = self.analyze_global(table) {return global;}
+            if let Some(constant) = builtins::get_constant(&self.name){
+                self.constant = Some(match (constant.value.parse::<i128>(), constant.value.parse::<f64>()){
+                    (Ok(value), _) if !is_float(&constant.var_type) => ConstValue::Int(value),
+                    (_, Ok(value)) => ConstValue::Float(value),
+                    _ => ConstValue::Symbol(constant.value.to_string()),
+                });
+                return Ok(constant.var_type);
+            }
+            // "Color.Red"
+            if let Some((enum_type, variant)) = table.get_variant(&self.name){
+                self.constant = Some(ConstValue::Symbol(variant_to_c(table, &enum_type, variant)?));
+                return Ok(enum_type);
+            }
+            // a function used as a value is a closure without any captured values
+            if let Ok(overloads) = table.get_functions(&self.name){
+                if overloads.len() > 1{
+                    return Err(format!("'{}' is overloaded, so it cannot be used as a value", self.name));
+                }
+                self.function = Some(overloads[0].clone());
+                return Ok(VarType::Function(overloads[0].arg_types.clone(), Box::new(overloads[0].return_type.clone())));
+            }
+            if table.get_generic(&self.name).is_some(){
+                return Err(format!("the generic function '{}' cannot be used as a value", self.name));
+            }
+            if builtins::is_builtin(&self.name){
+                return Err(format!("the built-in function '{}' cannot be used as a value", self.name));
+            }
+        }
+        return variable;
+    }
+}
+
+impl NodeVariableCall{
+    // null becomes an empty closure when it is used as a function
+    pub fn coerce(&mut self, value_type: VarType, target: &VarType) -> VarType{
+        if value_type == VarType::Pointer(Box::new(VarType::None)) && matches!(target, VarType::Function(_, _)){
+            self.constant = Some(ConstValue::Symbol(format!("(({}){{0}})", target.to_c().trim())));
+            return target.clone();
+        }
+        return value_type;
+    }
 }
 
 impl From<&Token> for NodeVariableCall{
     fn from(token: &Token) -> Self{
-        NodeVariableCall{name: get_token_value!(token, Token::Identifier).unwrap().to_string()}
+        NodeVariableCall::new(get_token_value!(token, Token::Identifier).unwrap().to_string())
     }
 }
 
@@ -32,6 +155,7 @@ pub struct NodeVariableInitialization{
     name: String,
     value: Box<Node>,
     var_type: VarType,
+    names: Vec<String>, // "auto (q, r) = value" - the tuple is kept in a hidden variable, its elements in these
 }
 
 impl NodeVariableInitialization{
@@ -40,6 +164,7 @@ impl NodeVariableInitialization{
             name: "".to_owned(),
             value: Box::new(Node::new()),
             var_type: VarType::new(),
+            names: Vec::new(),
         }
     }
 
@@ -48,26 +173,79 @@ impl NodeVariableInitialization{
        result.push_str(&self.name.to_owned());
        result.push_str(" = ");
        result.push_str(&self.value.to_c().to_owned());
+       if let VarType::Tuple(elements) = &self.var_type{
+           for (i, name) in self.names.iter().enumerate(){
+               if name == "_" {continue;}
+               result.push_str(&format!(";\n{}{} = {}._{}", elements[i].to_c(), name, self.name, i));
+           }
+       }
        return result;
     }
+
+    // "_" leaves an element out
+    fn analyze_destructuring(&mut self, table: &mut SymbolTable, value_type: VarType) -> Result<VarType, String>{
+        if self.var_type != VarType::Auto{
+            return Err("the variables a tuple is destructured into are declared with 'auto'".to_string());
+        }
+        let elements = match &value_type{
+            VarType::Tuple(elements) if elements.len() == self.names.len() => elements.clone(),
+            _ => return Err(format!("cannot destructure a value of type {} into {} variables", value_type, self.names.len())),
+        };
+        for (name, element) in self.names.iter().zip(elements){
+            if name != "_" {table.insert_variable(name, &element)?;}
+        }
+        self.var_type = value_type;
+        return Ok(VarType::None);
+    }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.var_type != VarType::Auto {self.var_type = table.resolve_type(&self.var_type)?;}
+        let mut value_type = self.value.analyze(table)?;
+        if value_type == VarType::None{
+            return Err(format!("cannot initialize '{}' with a value of type none", self.name));
+        }
+        if !self.names.is_empty() {return self.analyze_destructuring(table, value_type);}
+        if self.var_type == VarType::Auto && value_type == VarType::Pointer(Box::new(VarType::None)){
+            return Err(format!("cannot infer the type of '{}' from null", self.name));
+        }
+        if self.var_type == VarType::Auto && is_partial(&value_type){
+            return Err(format!("cannot infer the type of '{}' from a value of type {}", self.name, value_type));
+        }
+        if self.var_type == VarType::Auto {
+            self.var_type = value_type;
+        }else if {value_type = self.value.coerce(value_type, &self.var_type); true} && !is_compatible(&value_type, &self.var_type){
+            return Err(format!("cannot initialize '{}' of type {} with a value of type {}", self.name, self.var_type, value_type));
+        }
+        table.insert_variable(&self.name, &self.var_type)?;
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeVariableInitialization{
     fn from(tokens: &Vec<Token>) -> Self{
+        let mut i = 0;
+        let var_type = parse_type(tokens, &mut i);
         let mut result = NodeVariableInitialization {
-            name: get_token_value!(&tokens[1], Token::Identifier).unwrap().to_string(), 
+            name: String::new(),
             value: Box::new(Node::None),
-            var_type: VarType::from(*(get_token_value!(&tokens[0], Token::Keyword).unwrap()))
+            var_type: var_type,
+            names: Vec::new(),
         };
-
-        if tokens.len() > 4 {result.value = Box::new(Node::BinaryExpression(generate_binary_expression_tree(&tokens[3..].to_vec())));}
-        else {result.value = Box::new(Node::from(&tokens[3]));}
-
-        if result.var_type == VarType::Auto {
-            result.var_type = evaluate_var_type(&result.value);
+        if tokens[i] == Token::LPar{
+            let closing = i + get_closing(&tokens[i..]);
+            for name in split_arguments(&tokens[i + 1 .. closing]){
+                match name.as_slice(){
+                    [Token::Identifier(name)] => result.names.push(name.to_string()),
+                    _ => panic!("expected the names of the variables a tuple is destructured into"),
+                }
+            }
+            result.name = format!("__values_{}", result.names.join("_"));
+            i = closing;
+        }else{
+            result.name = get_token_value!(&tokens[i], Token::Identifier).unwrap().to_string();
         }
 
-        variables_insert(&result.name, &result.var_type);
+        result.value = Box::new(generate_expression(&tokens[i + 2..].to_vec()));
         return result;
     }
 }
@@ -90,25 +268,40 @@ impl NodeVariableDeclaration{
        let mut result: String = self.var_type.to_c().to_owned();
        result.push_str(&self.name.to_owned());
        result.push_str(" = ");
-       result.push_str(&self.var_type.to_c_default_value().to_owned());
+       // a list variable gets a list of its own right away, which the copies made of it share
+       match &self.var_type{
+           VarType::List(_element) => result.push_str(&format!("{}_new()", self.var_type.to_c().trim())),
+           _ => result.push_str(&self.var_type.to_c_default_value().to_owned()),
+       }
        return result;
     }
+
+    pub fn analyze(&mut self, table: &mut SymbolTable) -> Result<VarType, String>{
+        if self.var_type == VarType::Auto{
+            return Err(format!("cannot infer the type of '{}' without a value", self.name));
+        }
+        self.var_type = table.resolve_type(&self.var_type)?;
+        table.insert_variable(&self.name, &self.var_type)?;
+        return Ok(VarType::None);
+    }
 }
 
 impl From<&Vec<Token>> for NodeVariableDeclaration{
     fn from(tokens: &Vec<Token>) -> Self{
-        let result = NodeVariableDeclaration {
-            name: get_token_value!(&tokens[1], Token::Identifier).unwrap().to_string(), 
-            var_type: VarType::from(*(get_token_value!(&tokens[0], Token::Keyword).unwrap()))
-        };
-        variables_insert(&result.name, &result.var_type);
-        return result;
+        let mut i = 0;
+        let var_type = parse_type(tokens, &mut i);
+        NodeVariableDeclaration {
+            name: get_token_value!(&tokens[i], Token::Identifier).unwrap().to_string(), 
+            var_type: var_type,
+        }
     }
 
 }
 
 pub fn generate_variable(tokens: &Vec<Token>) -> Node{
-    // if tokens.len() < 3 panic
-    if tokens.len() < 3 {Node::VariableDeclaration(NodeVariableDeclaration::from(tokens))}
+    // the type is followed by the name and, if the variable is initialized, by '=' and the value
+    let mut i = 0;
+    parse_type(tokens, &mut i);
+    if tokens.len() < i + 2 {Node::VariableDeclaration(NodeVariableDeclaration::from(tokens))}
     else{Node::VariableInitialization(NodeVariableInitialization::from(tokens))}
 }
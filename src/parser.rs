@@ -4,8 +4,9 @@ extern crate regex;
 use crate::lexer::Token;
 use crate::lexer::Keyword;
 use crate::nodes::Node;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use crate::nodes::globals::NodeGlobal;
+use crate::runtime;
+use std::fmt;
 
 macro_rules! get_token_value{
     ($token: expr, $token_type: path) =>{
@@ -18,21 +19,7 @@ macro_rules! get_token_value{
 
 pub(crate) use get_token_value;
 
-// here we contain the different variables and their types - this is used
-// so we can easily format the print output
-lazy_static::lazy_static!{
-    static ref VARIABLES: Mutex<HashMap<String, VarType>> = Mutex::new(HashMap::<String, VarType>::new());
-}
-
-pub fn variables_insert(string: &String, var_type: &VarType){
-    VARIABLES.lock().unwrap().insert(string.to_string(), *var_type);
-}
-
-pub fn variables_get(string: &String) -> VarType{
-    *VARIABLES.lock().unwrap().get(string).unwrap()
-}
-
-#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+#[derive(Debug, PartialEq, Clone, Hash, Eq)]
 pub enum VarType{
     I8,
     I16,
@@ -45,6 +32,17 @@ pub enum VarType{
     F32,
     F64,
     Str,
+    Bool,
+    Array(Box<VarType>, usize), // [type; length]
+    List(Box<VarType>),         // list[type]
+    Tuple(Vec<VarType>),        // (type, type)
+    Option(Box<VarType>),       // option[type]
+    Result(Box<VarType>, Box<VarType>), // result[value type, error type]
+    Struct(String),             // the full name of the struct - "module.Name" outside of the main module
+    Enum(String),               // the full name of the enum, the same way
+    Pointer(Box<VarType>),      // *type - the type of null is a pointer to none
+    Function(Vec<VarType>, Box<VarType>), // fn(argument types) -> return type
+    Generic(String),            // a type parameter of a generic function, until it is called
     Auto,
     None,
 }
@@ -63,19 +61,83 @@ impl From<Keyword> for VarType{
             Keyword::F32  => return VarType::F32,
             Keyword::F64  => return VarType::F64,
             Keyword::Str  => return VarType::Str,
+            Keyword::Bool => return VarType::Bool,
             Keyword::Auto => return VarType::Auto,
             _             => return VarType::None, 
         }
     }
 }
 
+impl fmt::Display for VarType{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result{
+        let name = match self{
+            VarType::I8   => "i8",
+            VarType::I16  => "i16",
+            VarType::I32  => "i32",
+            VarType::I64  => "i64",
+            VarType::U8   => "u8",
+            VarType::U16  => "u16",
+            VarType::U32  => "u32",
+            VarType::U64  => "u64",
+            VarType::F32  => "f32",
+            VarType::F64  => "f64",
+            VarType::Str  => "str",
+            VarType::Bool => "bool",
+            VarType::Auto => "auto",
+            VarType::None => "none",
+            VarType::Array(element, length) => return write!(f, "[{}; {}]", element, length),
+            VarType::List(element) => return write!(f, "list[{}]", element),
+            VarType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.to_string()).collect();
+                return write!(f, "({})", elements.join(", "));
+            },
+            VarType::Option(value) => return write!(f, "option[{}]", value),
+            VarType::Result(value, error) => return write!(f, "result[{}, {}]", value, error),
+            VarType::Struct(name) => return write!(f, "{}", name),
+            VarType::Enum(name)   => return write!(f, "{}", name),
+            VarType::Generic(name) => return write!(f, "{}", name),
+            VarType::Pointer(target) if **target == VarType::None => "null",
+            VarType::Pointer(target) => return write!(f, "*{}", target),
+            VarType::Function(arg_types, return_type) => {
+                let arg_types: Vec<String> = arg_types.iter().map(|arg_type| arg_type.to_string()).collect();
+                return write!(f, "fn({}) -> {}", arg_types.join(", "), return_type);
+            },
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl VarType{
     pub fn new() -> Self{
         VarType::None
     }
 
+    // a name for the type which can be a part of a C identifier
+    pub fn mangle(&self) -> String{
+        match self{
+            VarType::Array(element, length) => return format!("array_{}_{}", element.mangle(), length),
+            VarType::List(element) => return format!("list_{}", element.mangle()),
+            // the number of elements keeps the nested tuples apart
+            VarType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| element.mangle()).collect();
+                return format!("tuple{}_{}", elements.len(), elements.join("_"));
+            },
+            VarType::Option(value) => return format!("option_{}", value.mangle()),
+            VarType::Result(value, error) => return format!("result_{}_{}", value.mangle(), error.mangle()),
+            VarType::Struct(name) => return name.replace('.', "__"),
+            VarType::Enum(name)   => return name.replace('.', "__"),
+            VarType::Pointer(target) => return format!("ptr_{}", target.mangle()),
+            VarType::Function(arg_types, return_type) => {
+                let arg_types: Vec<String> = arg_types.iter().map(|arg_type| arg_type.mangle()).collect();
+                return format!("fn_{}__{}", arg_types.join("_"), return_type.mangle());
+            },
+            _ => return self.to_string(),
+        }
+    }
+
+    // the composite types are wrapped in structs, declared the first time they are used
     pub fn to_c(&self) -> String{
-        match *self{
+        match self{
             VarType::I8   => return "char ".to_string(),
             VarType::I16  => return "int ".to_string(),
             VarType::I32  => return "long ".to_string(),
@@ -87,13 +149,83 @@ impl VarType{
             VarType::F32  => return "float ".to_string(),
             VarType::F64  => return "double ".to_string(),
             VarType::Str  => return "str ".to_string(),
+            VarType::Bool => return "_Bool ".to_string(),
             VarType::None => return "void ".to_string(),
+            VarType::Array(element, length) => {
+                let name: String = format!("__{}", self.mangle());
+                let definition: String = format!("typedef struct {{ {}data[{}]; }} {};\n", element.to_c(), length, name);
+                runtime::declare_type(&name, definition);
+                return name + " ";
+            },
+            // the list type comes with its own helper functions - "__list_i32_push" and so on
+            VarType::List(element) => {
+                let name: String = format!("__{}", self.mangle());
+                let definition: String = format!("__CHAL_LIST({}, {})\n", name, element.to_c().trim());
+                runtime::declare_type(&name, definition);
+                return name + " ";
+            },
+            // "typedef struct { long _0; _Bool _1; } __tuple2_i32_bool;"
+            VarType::Tuple(elements) => {
+                let name: String = format!("__{}", self.mangle());
+                let mut fields: String = String::new();
+                for (i, element) in elements.iter().enumerate(){
+                    fields.push_str(&format!("{}_{}; ", element.to_c(), i));
+                }
+                runtime::declare_type(&name, format!("typedef struct {{ {}}} {};\n", fields, name));
+                return name + " ";
+            },
+            // the tagged structs - "typedef struct { _Bool is_some; long value; } __option_i32;"
+            VarType::Option(value) => {
+                let name: String = format!("__{}", self.mangle());
+                let definition: String = format!("typedef struct {{ _Bool is_some; {}value; }} {};\n", value.to_c(), name);
+                runtime::declare_type(&name, definition);
+                return name + " ";
+            },
+            // the value and the error share their memory, result[none, E] only holds the error
+            VarType::Result(value, error) => {
+                let name: String = format!("__{}", self.mangle());
+                let fields: String = match **value{
+                    VarType::None => format!("{}error;", error.to_c()),
+                    _ => format!("union {{ {}value; {}error; }};", value.to_c(), error.to_c()),
+                };
+                runtime::declare_type(&name, format!("typedef struct {{ _Bool is_ok; {} }} {};\n", fields, name));
+                return name + " ";
+            },
+            // the structs and enums are declared by the semantic pass, which knows their fields
+            VarType::Struct(_name) => return self.mangle() + " ",
+            VarType::Enum(_name)   => return self.mangle() + " ",
+            VarType::Pointer(target) => return target.to_c() + "*",
+            // a closure - the C function and the values it captured, which it gets as its first argument -
+            // "typedef struct { long (*call)(void*, long); void* env; } __fn_i32__i32;", called with "__fn_i32__i32_call(f, x)"
+            VarType::Function(arg_types, return_type) => {
+                let name: String = format!("__{}", self.mangle());
+                let arg_types: Vec<String> = arg_types.iter().map(|arg_type| arg_type.to_c().trim().to_string()).collect();
+                let mut call_types: Vec<String> = vec!["void*".to_string()];
+                call_types.extend(arg_types.iter().cloned());
+                let definition: String = format!("typedef struct {{ {}(*call)({}); void* env; }} {};\n", return_type.to_c(), call_types.join(", "), name);
+                runtime::declare_type(&name, definition);
+
+                let mut params: String = format!("{} __function", name);
+                let mut args: String = "__function.env".to_string();
+                for (i, arg_type) in arg_types.iter().enumerate(){
+                    params.push_str(&format!(", {} __arg{}", arg_type, i));
+                    args.push_str(&format!(", __arg{}", i));
+                }
+                let call: String = if **return_type == VarType::None {""} else {"return "}.to_string();
+                runtime::declare_function(
+                    &format!("{}_call", name),
+                    format!("{}{}_call({})", return_type.to_c(), name, params),
+                    format!("{{\n__CHAL_CHECK_CALL(__function);\n{}__function.call({});\n}}\n", call, args),
+                );
+                return name + " ";
+            },
+            VarType::Generic(_name) => todo!(),
             VarType::Auto => todo!(),
         }
     }
 
     pub fn to_c_printf(&self) -> String{
-        match *self{
+        match self{
             VarType::I8   => return "%hd".to_string(),
             VarType::I16  => return "%d".to_string(),
             VarType::I32  => return "%ld".to_string(),
@@ -105,12 +237,13 @@ impl VarType{
             VarType::F32  => return "%f".to_string(),
             VarType::F64  => return "%lf".to_string(),
             VarType::Str  => return "%s".to_string(),
+            VarType::Bool => return "%s".to_string(), // printed as "true" or "false"
             _ => todo!(),
         }
     }
 
     pub fn to_c_default_value(&self) -> String{
-        match *self{
+        match self{
             VarType::I8   => return "0".to_string(),
             VarType::I16  => return "0".to_string(),
             VarType::I32  => return "0".to_string(),
@@ -122,11 +255,133 @@ impl VarType{
             VarType::F32  => return "0.0f".to_string(),
             VarType::F64  => return "0.0".to_string(),
             VarType::Str  => return "\"\"".to_string(),
+            VarType::Bool => return "0".to_string(),
+            VarType::Array(_element, _length) => return "{0}".to_string(),
+            VarType::List(_element) => return "{0}".to_string(),
+            VarType::Tuple(_elements) => return "{0}".to_string(),
+            VarType::Option(_value) => return "{0}".to_string(),
+            VarType::Result(_value, _error) => return "{0}".to_string(),
+            VarType::Struct(_name) => return "{0}".to_string(),
+            VarType::Enum(_name)   => return "{0}".to_string(),
+            VarType::Pointer(_target) => return "NULL".to_string(),
+            VarType::Function(_arg_types, _return_type) => return "{0}".to_string(),
             _ => todo!(),
         }
     }
 }
- 
+
+// "option[type]" or "result[type, type]"
+pub fn is_sum_type(tokens: &[Token], i: usize) -> bool{
+    match &tokens[i]{
+        Token::Identifier(name) if name == "option" || name == "result" => return tokens.get(i + 1) == Some(&Token::LBracket),
+        _ => return false,
+    }
+}
+
+// parses the type starting at tokens[*i] and moves the index past it
+pub fn parse_type(tokens: &Vec<Token>, i: &mut usize) -> VarType{
+    match &tokens[*i]{
+        Token::Mul => {
+            *i += 1;
+            return VarType::Pointer(Box::new(parse_type(tokens, i)));
+        },
+        // "**i32" is lexed as the exponent operator
+        Token::Exp => {
+            *i += 1;
+            return VarType::Pointer(Box::new(VarType::Pointer(Box::new(parse_type(tokens, i)))));
+        },
+        Token::Keyword(Keyword::List) => {
+            *i += 1;
+            if tokens[*i] != Token::LBracket {panic!("expected '[' after 'list'");}
+            *i += 1;
+            let element = parse_type(tokens, i);
+            if tokens[*i] != Token::RBracket {panic!("expected ']' after the element type of a list");}
+            *i += 1;
+            return VarType::List(Box::new(element));
+        },
+        Token::Keyword(Keyword::Fn) => {
+            *i += 1;
+            if tokens[*i] != Token::LPar {panic!("expected '(' after 'fn' in a function type");}
+            *i += 1;
+            let mut arg_types: Vec<VarType> = Vec::new();
+            while tokens[*i] != Token::RPar{
+                arg_types.push(parse_type(tokens, i));
+                if tokens[*i] == Token::Comma {*i += 1;}
+            }
+            *i += 1;
+            let mut return_type = VarType::None;
+            if tokens.get(*i) == Some(&Token::Return){
+                *i += 1;
+                return_type = parse_type(tokens, i);
+            }
+            return VarType::Function(arg_types, Box::new(return_type));
+        },
+        Token::Keyword(keyword) => {
+            *i += 1;
+            return VarType::from(*keyword);
+        },
+        Token::LPar => {
+            *i += 1;
+            let mut elements: Vec<VarType> = Vec::new();
+            while tokens[*i] != Token::RPar{
+                elements.push(parse_type(tokens, i));
+                if tokens[*i] == Token::Comma {*i += 1;}
+            }
+            *i += 1;
+            if elements.len() < 2 {panic!("a tuple type needs at least two types");}
+            return VarType::Tuple(elements);
+        },
+        Token::LBracket => {
+            *i += 1;
+            let element = parse_type(tokens, i);
+            if tokens[*i] != Token::Semicolon {panic!("expected ';' after the element type of an array");}
+            *i += 1;
+            let length: usize = match tokens[*i]{
+                Token::UInt8(val)  => val as usize,
+                Token::UInt16(val) => val as usize,
+                Token::UInt32(val) => val as usize,
+                Token::UInt64(val) => val as usize,
+                _ => panic!("expected the length of the array"),
+            };
+            *i += 1;
+            if tokens[*i] != Token::RBracket {panic!("expected ']' after the length of an array");}
+            *i += 1;
+            return VarType::Array(Box::new(element), length);
+        },
+        // not keywords, so that they can still name variables
+        Token::Identifier(name) if is_sum_type(tokens, *i) => {
+            let name: String = name.to_string();
+            *i += 2;
+            let value = parse_type(tokens, i);
+            if name == "option"{
+                if tokens[*i] != Token::RBracket {panic!("expected ']' after the value type of an option");}
+                *i += 1;
+                return VarType::Option(Box::new(value));
+            }
+            if tokens[*i] != Token::Comma {panic!("expected ',' after the value type of a result");}
+            *i += 1;
+            let error = parse_type(tokens, i);
+            if tokens[*i] != Token::RBracket {panic!("expected ']' after the error type of a result");}
+            *i += 1;
+            return VarType::Result(Box::new(value), Box::new(error));
+        },
+        // the structs of other modules are qualified - "module.Name"
+        Token::Identifier(name) => {
+            let mut name: String = name.to_string();
+            *i += 1;
+            while *i + 1 < tokens.len() && tokens[*i] == Token::Dot{
+                match &tokens[*i + 1]{
+                    Token::Identifier(val) => name = format!("{}.{}", name, val),
+                    _ => break,
+                }
+                *i += 2;
+            }
+            return VarType::Struct(name);
+        },
+        token => panic!("expected a type, found {:?}", token),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum OperatorType{
     Plus,     // +
@@ -151,6 +406,8 @@ pub enum OperatorType{
     And,      // &&
     Or,       // |
     Not,      // !
+    Reference,   // &
+    Dereference, // *
     // todo!() - ExpEq (**=), FloorDivEq ( //=), BinaryAnd (&), BinaryOr (|)
     None,
 }
@@ -180,6 +437,8 @@ impl From<&Token> for OperatorType{
             Token::And      => return OperatorType::And,
             Token::Or       => return OperatorType::Or,
             Token::Not      => return OperatorType::Not,
+            Token::Reference   => return OperatorType::Reference,
+            Token::Dereference => return OperatorType::Dereference,
             _               => return OperatorType::None,
         }
     }
@@ -190,6 +449,34 @@ impl OperatorType{
         OperatorType::None
     }
 
+    pub fn is_assignment(&self) -> bool{
+        match *self{
+            OperatorType::Eq      => return true,
+            OperatorType::PlusEq  => return true,
+            OperatorType::MinusEq => return true,
+            OperatorType::MulEq   => return true,
+            OperatorType::DivEq   => return true,
+            OperatorType::ModEq   => return true,
+            _ => return false,
+        }
+    }
+
+    // the operators whose result is a truth value (an int in C)
+    pub fn is_logical(&self) -> bool{
+        match *self{
+            OperatorType::EqEq  => return true,
+            OperatorType::NotEq => return true,
+            OperatorType::Lt    => return true,
+            OperatorType::Gt    => return true,
+            OperatorType::LtEq  => return true,
+            OperatorType::GtEq  => return true,
+            OperatorType::And   => return true,
+            OperatorType::Or    => return true,
+            OperatorType::Not   => return true,
+            _ => return false,
+        }
+    }
+
     pub fn to_c(&self) -> String{
         match *self{
             OperatorType::Plus       => return "+ ".to_string(),
@@ -214,11 +501,48 @@ impl OperatorType{
             OperatorType::And        => return "&& ".to_string(),
             OperatorType::Or         => return "|| ".to_string(),
             OperatorType::Not        => return "! ".to_string(),
+            OperatorType::Reference  => return "&".to_string(),
+            OperatorType::Dereference => return "*".to_string(),
             _ => return "".to_string(),
         }
     }
 }
 
+// whether the 'fn' at the given index starts a lambda - "fn(x: i32) -> i32: x * k end" - and not a function
+// type, the types never name their arguments and are never followed by ':' (unless they are a return type)
+pub fn is_lambda(tokens: &[Token], i: usize) -> bool{
+    if tokens.get(i + 1) != Some(&Token::LPar) {return false;}
+    if let (Some(Token::Identifier(_)), Some(Token::Colon)) = (tokens.get(i + 2), tokens.get(i + 3)) {return true;}
+    if tokens.get(i + 2) != Some(&Token::RPar) || (i > 0 && tokens[i - 1] == Token::Return) {return false;}
+    let mut j = i + 3;
+    if tokens.get(j) == Some(&Token::Return){
+        j += 1;
+        parse_type(&tokens.to_vec(), &mut j);
+    }
+    return tokens.get(j) == Some(&Token::Colon);
+}
+
+// whether the token at the given index starts a block closed by an 'end' - "fn(i32) -> i32" is a type
+// and the C functions declared with 'extern' have no body
+pub fn opens_block(tokens: &[Token], i: usize) -> bool{
+    match &tokens[i]{
+        Token::Keyword(Keyword::If)     => return true,
+        Token::Keyword(Keyword::While)  => return true,
+        Token::Keyword(Keyword::For)    => return true,
+        Token::Keyword(Keyword::Fn) if i > 0 && tokens[i - 1] == Token::Keyword(Keyword::Extern) => return false,
+        Token::Keyword(Keyword::Fn)     => return tokens.get(i + 1) != Some(&Token::LPar) || is_lambda(tokens, i),
+        Token::Keyword(Keyword::Struct) => return true,
+        Token::Keyword(Keyword::Enum)   => return true,
+        Token::Keyword(Keyword::Match)  => return true,
+        // the lexer leaves the lines of an inline C block in a single token, right after its first line
+        Token::Identifier(name) if name == "c" => {
+            let line_end: usize = i + tokens[i..].iter().position(|token| *token == Token::NewLine).unwrap_or(tokens.len() - i);
+            return matches!(tokens.get(line_end + 1), Some(Token::CCode(_)));
+        },
+        _ => return false,
+    }
+}
+
 pub fn split_tokens(tokens: Vec<Token>) -> Vec<Vec<Token>>{
     let mut openings: u8; // how many blocks we are in
     let mut current: Vec<Token>;
@@ -228,14 +552,8 @@ pub fn split_tokens(tokens: Vec<Token>) -> Vec<Vec<Token>>{
         openings = 0;
         current = Vec::new();
         loop{
-            match tokens[i]{
-                Token::Keyword(Keyword::If)    => openings += 1,       
-                Token::Keyword(Keyword::While) => openings += 1,
-                Token::Keyword(Keyword::For)   => openings += 1,
-                Token::Keyword(Keyword::Fn)    => openings += 1,
-                Token::Keyword(Keyword::End)   => openings -= 1,
-                _ => (),
-            }
+            if opens_block(&tokens, i) {openings += 1;}
+            if tokens[i] == Token::Keyword(Keyword::End) {openings -= 1;}
             i += 1;
             if openings == 0 && tokens[i] == Token::NewLine {break;}
             current.push(tokens[i].clone());
@@ -245,13 +563,19 @@ pub fn split_tokens(tokens: Vec<Token>) -> Vec<Vec<Token>>{
     return result;
 }
 
-pub fn parse(tokens: Vec<Token>) -> Vec<Node>{
+pub fn parse(mut tokens: Vec<Token>) -> Vec<Node>{
+    // split_tokens() expects every block to be preceded by a new line
+    tokens.insert(0, Token::NewLine);
     let token_blocks = split_tokens(tokens);
     let mut result: Vec<Node> = Vec::new();
     for i in token_blocks{ 
         //result.push(generate_function(&tokens[i..].to_vec()));
         if i == Vec::new() {continue;}
-        result.push(Node::from(i));
+        // the variables of the top level are the globals of the module
+        match Node::from(i.clone()){
+            Node::VariableInitialization(_) | Node::VariableDeclaration(_) => result.push(Node::Global(NodeGlobal::from(&i))),
+            node => result.push(node),
+        }
     }
     return result;
 }
@@ -0,0 +1,15 @@
+// the bounds checks are left out of the release builds
+#ifdef NDEBUG
+#define __CHAL_INDEX(index, length) (index)
+#else
+#define __CHAL_INDEX(index, length) __chal_check_index((index), (length))
+#endif
+
+__CHAL_LOCAL size_t __chal_check_index(long long index, size_t length){
+    if(index < 0 || (size_t)index >= length){
+        fprintf(stderr, "error: index %lld is out of bounds for length %zu\n", index, length);
+        exit(1);
+    }
+    return index;
+}
+
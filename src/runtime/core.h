@@ -0,0 +1,26 @@
+#include<stdio.h>
+#include<stdlib.h>
+#include<stdarg.h>
+
+typedef char* str;
+
+// the linkage of what the program defines without exporting it - a library keeps it to itself
+// ('static'), so that two libraries built by chal can be linked into the same program
+#ifndef __CHAL_LOCAL
+#define __CHAL_LOCAL
+#endif
+
+// formats the interpolated strings which are not printed directly
+__CHAL_LOCAL str __chal_format(const char* format, ...){
+    va_list args;
+    va_start(args, format);
+    int length = vsnprintf(NULL, 0, format, args);
+    va_end(args);
+
+    str result = malloc(length + 1);
+    va_start(args, format);
+    vsnprintf(result, length + 1, format, args);
+    va_end(args);
+    return result;
+}
+
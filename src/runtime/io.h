@@ -0,0 +1,16 @@
+__CHAL_LOCAL str __chal_input(void){
+    fflush(stdout);
+    size_t capacity = 64, length = 0;
+    str line = malloc(capacity);
+    int c;
+    while((c = getchar()) != EOF && c != '\n'){
+        if(length + 1 == capacity){
+            capacity *= 2;
+            line = realloc(line, capacity);
+        }
+        line[length++] = c;
+    }
+    if(length > 0 && line[length - 1] == '\r') length--;
+    line[length] = '\0';
+    return line;
+}
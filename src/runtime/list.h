@@ -0,0 +1,102 @@
+#include<string.h>
+
+// defines the list type NAME holding values of type T, with its helper functions - every
+// element type used by the program gets its own instance. A list is a handle to a header on the
+// heap, so its copies (the arguments of the functions included) are the same list and see it grow;
+// a handle which was never given a list (the list fields of a zero-initialized struct) is an
+// empty list, which the functions changing it create the first time (they take the handle's address)
+#define __CHAL_LIST(NAME, T) \
+typedef struct { T* data; size_t length; size_t capacity; } NAME##_header; \
+typedef NAME##_header* NAME; \
+\
+__CHAL_LOCAL NAME NAME##_new(void){ \
+    NAME list = calloc(1, sizeof(NAME##_header)); \
+    if(list == NULL){ \
+        fprintf(stderr, "error: out of memory\n"); \
+        exit(1); \
+    } \
+    return list; \
+} \
+\
+__CHAL_LOCAL NAME NAME##_get(NAME* list){ \
+    if(*list == NULL) *list = NAME##_new(); \
+    return *list; \
+} \
+\
+__CHAL_LOCAL void NAME##_reserve(NAME list, size_t capacity){ \
+    if(capacity <= list->capacity) return; \
+    if(capacity < list->capacity * 2) capacity = list->capacity * 2; \
+    if(capacity < 4) capacity = 4; \
+    list->data = realloc(list->data, capacity * sizeof(T)); \
+    if(list->data == NULL){ \
+        fprintf(stderr, "error: out of memory\n"); \
+        exit(1); \
+    } \
+    list->capacity = capacity; \
+} \
+\
+__CHAL_LOCAL NAME NAME##_from(T* items, size_t length){ \
+    NAME list = NAME##_new(); \
+    NAME##_reserve(list, length); \
+    memcpy(list->data, items, length * sizeof(T)); \
+    list->length = length; \
+    return list; \
+} \
+\
+__CHAL_LOCAL size_t NAME##_len(NAME list){ \
+    return list == NULL ? 0 : list->length; \
+} \
+\
+__CHAL_LOCAL T* NAME##_at(NAME list, long long index){ \
+    return &list->data[__CHAL_INDEX(index, NAME##_len(list))]; \
+} \
+\
+__CHAL_LOCAL void NAME##_push(NAME* handle, T value){ \
+    NAME list = NAME##_get(handle); \
+    NAME##_reserve(list, list->length + 1); \
+    list->data[list->length++] = value; \
+} \
+\
+__CHAL_LOCAL T NAME##_pop(NAME* handle){ \
+    NAME list = NAME##_get(handle); \
+    if(list->length == 0){ \
+        fprintf(stderr, "error: pop from an empty list\n"); \
+        exit(1); \
+    } \
+    return list->data[--list->length]; \
+} \
+\
+__CHAL_LOCAL void NAME##_insert(NAME* handle, long long index, T value){ \
+    NAME list = NAME##_get(handle); \
+    if(index < 0 || (size_t)index > list->length){ \
+        fprintf(stderr, "error: cannot insert at index %lld of a list of length %zu\n", index, list->length); \
+        exit(1); \
+    } \
+    NAME##_reserve(list, list->length + 1); \
+    memmove(&list->data[index + 1], &list->data[index], (list->length - index) * sizeof(T)); \
+    list->data[index] = value; \
+    list->length++; \
+} \
+\
+__CHAL_LOCAL T NAME##_remove(NAME* handle, long long index){ \
+    NAME list = NAME##_get(handle); \
+    if(index < 0 || (size_t)index >= list->length){ \
+        fprintf(stderr, "error: cannot remove index %lld of a list of length %zu\n", index, list->length); \
+        exit(1); \
+    } \
+    T value = list->data[index]; \
+    memmove(&list->data[index], &list->data[index + 1], (list->length - index - 1) * sizeof(T)); \
+    list->length--; \
+    return value; \
+} \
+\
+/* the elements are released and the list is left empty - the header stays, as the other */ \
+/* handles to the list may still use it */ \
+__CHAL_LOCAL void NAME##_free(NAME* handle){ \
+    NAME list = *handle; \
+    if(list == NULL) return; \
+    free(list->data); \
+    list->data = NULL; \
+    list->length = 0; \
+    list->capacity = 0; \
+}
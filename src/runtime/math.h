@@ -0,0 +1,19 @@
+#include<math.h>
+
+#define __CHAL_NUMERIC(NAME, T) \
+    __CHAL_LOCAL T __chal_abs_##NAME(T x){ return x < 0 ? -x : x; } \
+    __CHAL_LOCAL T __chal_min_##NAME(T a, T b){ return a < b ? a : b; } \
+    __CHAL_LOCAL T __chal_max_##NAME(T a, T b){ return a > b ? a : b; } \
+    __CHAL_LOCAL T __chal_clamp_##NAME(T x, T low, T high){ return x < low ? low : (x > high ? high : x); }
+
+__CHAL_NUMERIC(i8, char)
+__CHAL_NUMERIC(i16, int)
+__CHAL_NUMERIC(i32, long)
+__CHAL_NUMERIC(i64, long long)
+__CHAL_NUMERIC(u8, unsigned char)
+__CHAL_NUMERIC(u16, unsigned int)
+__CHAL_NUMERIC(u32, unsigned long)
+__CHAL_NUMERIC(u64, unsigned long long)
+__CHAL_NUMERIC(f32, float)
+__CHAL_NUMERIC(f64, double)
+
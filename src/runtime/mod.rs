@@ -0,0 +1,135 @@
+use std::sync::Mutex;
+
+// the C code every generated program starts with - the implementations
+// of the built-in functions which are not a single C expression
+pub fn prelude() -> String{
+    let mut result: String = include_str!("core.h").to_string();
+    result.push_str(include_str!("string.h"));
+    result.push_str(include_str!("io.h"));
+    result.push_str(include_str!("math.h"));
+    result.push_str(include_str!("array.h"));
+    result.push_str(include_str!("pointer.h"));
+    result.push_str(include_str!("list.h"));
+    return result;
+}
+
+// the struct types generated for the composite types of the program, in the order
+// they have to be declared (the types they depend on are always declared first)
+lazy_static::lazy_static!{
+    static ref TYPES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+}
+
+pub fn declare_type(name: &String, definition: String){
+    let mut types = TYPES.lock().unwrap();
+    if !types.iter().any(|(declared, _definition)| declared == name){
+        types.push((name.to_string(), definition));
+    }
+}
+
+pub fn type_declarations() -> String{
+    return TYPES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_name, definition)| definition.to_string())
+        .collect();
+}
+
+// the definitions of the given types only, for the header of a library
+pub fn type_declarations_of(names: &Vec<String>) -> String{
+    return TYPES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(name, _definition)| names.contains(name))
+        .map(|(_name, definition)| definition.to_string())
+        .collect();
+}
+
+// the C functions the compiler makes itself - the helpers of the function types and the
+// lambdas, lifted out of the functions they are written in - by their names, with their
+// signatures and their bodies
+lazy_static::lazy_static!{
+    static ref FUNCTIONS: Mutex<Vec<(String, String, String)>> = Mutex::new(Vec::new());
+}
+
+pub fn declare_function(name: &String, signature: String, body: String){
+    let mut functions = FUNCTIONS.lock().unwrap();
+    if !functions.iter().any(|(declared, _signature, _body)| declared == name){
+        functions.push((name.to_string(), signature, body));
+    }
+}
+
+pub fn function_prototypes() -> String{
+    return FUNCTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_name, signature, _body)| format!("__CHAL_LOCAL {};\n", signature))
+        .collect();
+}
+
+pub fn function_definitions() -> String{
+    return FUNCTIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_name, signature, body)| format!("__CHAL_LOCAL {}{}", signature, body))
+        .collect();
+}
+
+// the functions the headers of the prelude declare - an 'extern fn' with one of their names is
+// already declared, with the C types, and must not be declared again from its signature
+const PRELUDE_FUNCTIONS: &[&str] = &[
+    // <stdio.h>
+    "remove", "rename", "tmpfile", "tmpnam", "fclose", "fflush", "fopen", "freopen", "setbuf", "setvbuf",
+    "fprintf", "fscanf", "printf", "scanf", "snprintf", "sprintf", "sscanf", "vfprintf", "vfscanf", "vprintf",
+    "vscanf", "vsnprintf", "vsprintf", "vsscanf", "fgetc", "fgets", "fputc", "fputs", "getc", "getchar",
+    "putc", "putchar", "puts", "ungetc", "fread", "fwrite", "fgetpos", "fseek", "fsetpos", "ftell", "rewind",
+    "clearerr", "feof", "ferror", "perror", "fileno", "fdopen", "popen", "pclose", "getline", "getdelim",
+    "dprintf", "fseeko", "ftello", "vdprintf", "getw", "putw", "ctermid", "tempnam", "flockfile", "funlockfile",
+    "getc_unlocked", "getchar_unlocked", "putc_unlocked", "putchar_unlocked", "open_memstream", "fmemopen",
+    // <stdlib.h>
+    "atof", "atoi", "atol", "atoll", "strtod", "strtof", "strtold", "strtol", "strtoll", "strtoul", "strtoull",
+    "rand", "srand", "calloc", "free", "malloc", "realloc", "aligned_alloc", "abort", "atexit", "at_quick_exit",
+    "exit", "_Exit", "quick_exit", "getenv", "setenv", "unsetenv", "system", "bsearch", "qsort", "abs", "labs",
+    "llabs", "div", "ldiv", "lldiv", "mblen", "mbtowc", "wctomb", "mbstowcs", "wcstombs", "realpath", "mkstemp",
+    "random", "srandom", "posix_memalign", "rand_r", "drand48", "erand48", "lrand48", "nrand48", "mrand48",
+    "jrand48", "srand48", "seed48", "putenv", "mktemp", "mkdtemp", "initstate", "setstate", "a64l", "l64a",
+    "ecvt", "fcvt", "gcvt", "valloc", "alloca",
+    // <string.h>
+    "memcpy", "memmove", "strcpy", "strncpy", "strcat", "strncat", "memcmp", "strcmp", "strcoll", "strncmp",
+    "strxfrm", "memchr", "strchr", "strcspn", "strpbrk", "strrchr", "strspn", "strstr", "strtok", "memset",
+    "strerror", "strlen", "strnlen", "strdup", "strndup", "strtok_r", "strcasecmp", "strncasecmp",
+    "strerror_r", "strsignal", "strsep", "stpcpy", "stpncpy", "memccpy",
+    // <strings.h>, which <string.h> includes
+    "index", "rindex", "bcmp", "bcopy", "bzero", "ffs",
+    // <ctype.h>
+    "isalnum", "isalpha", "isblank", "iscntrl", "isdigit", "isgraph", "islower", "isprint", "ispunct", "isspace",
+    "isupper", "isxdigit", "tolower", "toupper",
+];
+
+// <math.h> declares them with an 'f' (float) and an 'l' (long double) version as well - "sqrtf"
+const MATH_FUNCTIONS: &[&str] = &[
+    "acos", "asin", "atan", "atan2", "cos", "sin", "tan", "acosh", "asinh", "atanh", "cosh", "sinh", "tanh",
+    "exp", "exp2", "expm1", "frexp", "ilogb", "ldexp", "log", "log10", "log1p", "log2", "logb", "modf", "scalbn",
+    "scalbln", "cbrt", "fabs", "hypot", "pow", "sqrt", "erf", "erfc", "lgamma", "tgamma", "ceil", "floor",
+    "nearbyint", "rint", "lrint", "llrint", "round", "lround", "llround", "trunc", "fmod", "remainder", "remquo",
+    "copysign", "nan", "nextafter", "nexttoward", "fdim", "fmax", "fmin", "fma", "j0", "j1", "jn", "y0", "y1",
+    "yn", "gamma", "drem", "finite", "significand", "isinf", "isnan",
+];
+
+pub fn prelude_declares(name: &str) -> bool{
+    if PRELUDE_FUNCTIONS.contains(&name) || MATH_FUNCTIONS.contains(&name) {return true;}
+    return [name.strip_suffix('f'), name.strip_suffix('l')].iter().flatten().any(|name| MATH_FUNCTIONS.contains(name));
+}
+
+// the types the headers of the prelude declare, and its own
+const PRELUDE_TYPES: &[&str] = &[
+    "FILE", "fpos_t", "size_t", "ssize_t", "off_t", "ptrdiff_t", "wchar_t", "div_t", "ldiv_t", "lldiv_t",
+    "va_list", "float_t", "double_t", "str",
+];
+
+pub fn prelude_declares_type(name: &str) -> bool{
+    return PRELUDE_TYPES.contains(&name);
+}
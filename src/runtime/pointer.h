@@ -0,0 +1,31 @@
+// the null checks are left out of the release builds
+#ifdef NDEBUG
+#define __CHAL_CHECK_NULL(pointer) (pointer)
+#define __CHAL_CHECK_CALL(function)
+#else
+#define __CHAL_CHECK_NULL(pointer) __chal_check_null(pointer)
+#define __CHAL_CHECK_CALL(function) if((function).call == NULL) __chal_null_call()
+#endif
+
+__CHAL_LOCAL void* __chal_check_null(void* pointer){
+    if(pointer == NULL){
+        fprintf(stderr, "error: null pointer dereference\n");
+        exit(1);
+    }
+    return pointer;
+}
+
+__CHAL_LOCAL void __chal_null_call(){
+    fprintf(stderr, "error: call of a null function\n");
+    exit(1);
+}
+
+// the memory of alloc() starts zeroed
+__CHAL_LOCAL void* __chal_alloc(size_t count, size_t size){
+    void* memory = calloc(count, size);
+    if(memory == NULL && count != 0){
+        fprintf(stderr, "error: out of memory\n");
+        exit(1);
+    }
+    return memory;
+}
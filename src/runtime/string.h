@@ -0,0 +1,34 @@
+#include<string.h>
+#include<ctype.h>
+#include<errno.h>
+
+__CHAL_LOCAL str __chal_concat(str a, str b){
+    size_t length_a = strlen(a), length_b = strlen(b);
+    str result = malloc(length_a + length_b + 1);
+    memcpy(result, a, length_a);
+    memcpy(result + length_a, b, length_b + 1);
+    return result;
+}
+
+// a number is valid only if nothing but whitespace follows it
+static int __chal_parse_rest_failed(str s, char* end){
+    if(end == s) return 1;
+    while(isspace((unsigned char)*end)) end++;
+    return *end != '\0';
+}
+
+// the parses return whether they succeeded, the value is written to the pointer - the
+// parse_int() and parse_float() of the program make a result of it
+__CHAL_LOCAL _Bool __chal_parse_int(str s, long long* value){
+    char* end;
+    errno = 0;
+    *value = strtoll(s, &end, 10);
+    return !__chal_parse_rest_failed(s, end) && errno != ERANGE;
+}
+
+__CHAL_LOCAL _Bool __chal_parse_float(str s, double* value){
+    char* end;
+    errno = 0;
+    *value = strtod(s, &end);
+    return !__chal_parse_rest_failed(s, end) && errno != ERANGE;
+}
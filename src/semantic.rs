@@ -0,0 +1,1175 @@
+use crate::parser::VarType;
+use crate::builtins;
+use crate::modules::Module;
+use crate::nodes::Node;
+use crate::nodes::globals::ConstValue;
+use crate::runtime;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct FunctionSignature{
+    pub arg_names: Vec<String>,
+    pub arg_types: Vec<VarType>,
+    pub defaults: Vec<Option<Node>>, // the analyzed default values, known once the globals are
+    pub return_type: VarType,
+    pub c_name: String, // filled in when the function is inserted in the table
+    pub unmangled: bool, // declared with 'extern' or 'export', it keeps its name in C
+}
+
+// a function with type parameters - "fn max[T: numeric](a: T, b: T) -> T" - which is analyzed
+// and generated again for every combination of types it is called with
+#[derive(Debug, Clone)]
+pub struct GenericFunction{
+    pub type_params: Vec<(String, Option<String>)>, // the names and the constraints
+    pub signature: FunctionSignature, // the type parameters are left in its types
+    pub definition: Node, // as it was parsed
+}
+
+// a generic function called with the given types, waiting to be analyzed
+#[derive(Debug)]
+pub struct Instance{
+    pub name: String, // "max[i32]", for the error messages
+    pub module: String,
+    pub c_name: String,
+    pub type_params: Vec<(String, VarType)>,
+    pub definition: Node,
+    pub chain: Vec<String>, // the instances which called for this one, and this one last
+}
+
+// how deep the instances calling for new instances can go - a function calling itself with a
+// bigger type ("rec([x])") would otherwise never run out of new instances
+const MAX_INSTANCE_DEPTH: usize = 32;
+
+// the constraints the type parameters can have
+pub const CONSTRAINTS: [&str; 2] = ["numeric", "comparable"];
+
+// a variable defined at the top level of a module
+#[derive(Debug, Clone)]
+pub struct Global{
+    pub var_type: VarType,
+    pub value: Option<ConstValue>, // the folded value of a constant, the mutable globals have none
+    pub c_name: String,
+}
+
+pub type StructFields = Vec<(String, VarType)>;
+pub type EnumVariants = Vec<(String, StructFields)>; // the values every variant carries
+
+// the namespace of a single source file
+#[derive(Debug)]
+struct ModuleSymbols{
+    functions: HashMap<String, Vec<FunctionSignature>>, // every overload of the name
+    generics: HashMap<String, GenericFunction>,
+    globals: HashMap<String, Global>,
+    types: Vec<String>, // the structs and the enums
+    imports: Vec<String>,
+    prefix: String, // prepended to the C names, so the modules can't clash with each other (or with C)
+    is_main: bool,
+    includes: bool, // whether it includes C headers, which declare its 'extern' functions
+}
+
+// keeps track of everything that is visible at the current point of the program -
+// the variables of every nested block and the functions of every module analyzed so far
+#[derive(Debug)]
+pub struct SymbolTable{
+    scopes: Vec<HashMap<String, VarType>>,
+    // the statements deferred in every scope so far, run when the scope is left
+    defers: Vec<Vec<Node>>,
+    loops: Vec<usize>, // the scopes of the bodies of the loops being analyzed, innermost last
+    modules: HashMap<String, ModuleSymbols>,
+    // the fields of every struct and the variants of every enum, by their full names -
+    // "Point" in the main module and "module.Point" elsewhere
+    structs: HashMap<String, StructFields>,
+    enums: HashMap<String, EnumVariants>,
+    current_module: String,
+    // the lambdas being analyzed, innermost last - the scope of their arguments and
+    // the variables of the enclosing functions they use
+    lambdas: Vec<(usize, Vec<(String, VarType)>)>,
+    type_params: Vec<(String, VarType)>, // the types of the generic function being analyzed
+    instances: Vec<String>, // the C names of every instance of the generic functions
+    pending: Vec<Instance>,
+    instance_chain: Vec<String>, // of the instance being analyzed
+    pub return_type: Option<VarType>, // of the function being analyzed
+    pub libraries: Vec<String>, // the C libraries the used built-in functions need
+    pub headers: Vec<String>, // the C headers declaring the 'extern' functions
+    pub exported_types: Vec<String>, // the C types the generated header needs, for the 'export' functions
+}
+
+impl SymbolTable{
+    pub fn new() -> Self{
+        SymbolTable {
+            scopes: vec![HashMap::new()],
+            defers: vec![Vec::new()],
+            loops: Vec::new(),
+            modules: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            current_module: String::new(),
+            lambdas: Vec::new(),
+            type_params: Vec::new(),
+            instances: Vec::new(),
+            pending: Vec::new(),
+            instance_chain: Vec::new(),
+            return_type: None,
+            libraries: Vec::new(),
+            headers: Vec::new(),
+            exported_types: Vec::new(),
+        }
+    }
+
+    // the functions and the globals of the main module get a prefix as well, the C library
+    // the prelude includes already has an 'index' and a 'div'
+    pub fn enter_module(&mut self, name: &String, is_main: bool){
+        let prefix = if is_main {"__main__".to_string()} else {format!("{}__", name)};
+        self.modules.insert(name.to_string(), ModuleSymbols {functions: HashMap::new(), generics: HashMap::new(), globals: HashMap::new(), types: Vec::new(), imports: Vec::new(), prefix: prefix, is_main: is_main, includes: false});
+        self.current_module = name.to_string();
+        self.scopes = vec![HashMap::new()];
+        self.defers = vec![Vec::new()];
+    }
+
+    pub fn import(&mut self, module: &String){
+        let imports = &mut self.modules.get_mut(&self.current_module).unwrap().imports;
+        if imports.contains(module) {return;}
+        imports.push(module.to_string());
+    }
+
+    pub fn push_scope(&mut self){
+        self.scopes.push(HashMap::new());
+        self.defers.push(Vec::new());
+    }
+
+    pub fn pop_scope(&mut self){
+        self.scopes.pop();
+        self.defers.pop();
+    }
+
+    pub fn defer(&mut self, statement: Node){
+        self.defers.last_mut().unwrap().push(statement);
+    }
+
+    // what has to run before leaving every scope from the given one on, the last deferred first
+    pub fn deferred(&self, base: usize) -> Vec<Node>{
+        return self.defers[base..].iter().rev().flat_map(|statements| statements.iter().rev().cloned()).collect();
+    }
+
+    // the scope a return leaves, the one of the innermost lambda or of the function
+    pub fn function_scope(&self) -> usize{
+        match self.lambdas.last(){
+            Some((base, _captures)) => return *base,
+            None => return 0,
+        }
+    }
+
+    // called before the scope of the body of a loop is pushed
+    pub fn enter_loop(&mut self){
+        self.loops.push(self.scopes.len());
+    }
+
+    pub fn exit_loop(&mut self){
+        self.loops.pop();
+    }
+
+    // the scope a break leaves - the loops outside of the innermost lambda can't be broken out of
+    pub fn loop_scope(&self) -> Option<usize>{
+        return self.loops.last().cloned().filter(|scope| *scope > self.function_scope());
+    }
+
+    pub fn insert_variable(&mut self, name: &String, var_type: &VarType) -> Result<(), String>{
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.contains_key(name){
+            return Err(format!("variable '{}' is already declared in this scope", name));
+        }
+        scope.insert(name.to_string(), var_type.clone());
+        return Ok(());
+    }
+
+    fn find_variable(&self, name: &String) -> Option<(usize, VarType)>{
+        for (i, scope) in self.scopes.iter().enumerate().rev(){
+            if let Some(var_type) = scope.get(name) {return Some((i, var_type.clone()));}
+        }
+        return None;
+    }
+
+    // a variable declared outside of the lambdas it is used in is captured by every one of them
+    pub fn get_variable(&mut self, name: &String) -> Result<VarType, String>{
+        let (scope, var_type) = match self.find_variable(name){
+            Some(variable) => variable,
+            None => return Err(format!("unknown variable '{}'", name)),
+        };
+        for (base, captures) in self.lambdas.iter_mut(){
+            if scope < *base && !captures.iter().any(|(captured, _var_type)| captured == name){
+                captures.push((name.to_string(), var_type.clone()));
+            }
+        }
+        return Ok(var_type);
+    }
+
+    // whether the variable belongs to a function enclosing the innermost lambda
+    pub fn is_captured(&self, name: &String) -> bool{
+        match (self.lambdas.last(), self.find_variable(name)){
+            (Some((base, _captures)), Some((scope, _var_type))) => return scope < *base,
+            _ => return false,
+        }
+    }
+
+    pub fn enter_lambda(&mut self){
+        self.push_scope();
+        self.lambdas.push((self.scopes.len() - 1, Vec::new()));
+    }
+
+    // returns the variables the lambda captured
+    pub fn exit_lambda(&mut self) -> Vec<(String, VarType)>{
+        self.pop_scope();
+        return self.lambdas.pop().unwrap().1;
+    }
+
+    // returns the signature with its C name filled in, which changes if the function gets overloaded
+    pub fn insert_function(&mut self, name: &String, mut signature: FunctionSignature) -> Result<FunctionSignature, String>{
+        if builtins::is_builtin(name){
+            return Err(format!("'{}' is a built-in function and cannot be redefined", name));
+        }
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        if module.globals.contains_key(name){
+            return Err(format!("'{}' is already defined as a global variable", name));
+        }
+        if module.generics.contains_key(name){
+            return Err(format!("'{}' is already defined as a generic function", name));
+        }
+        let overloads = module.functions.entry(name.to_string()).or_default();
+        if overloads.iter().any(|overload| overload.arg_types == signature.arg_types){
+            return Err(format!("function '{}({})' is already defined", name, type_list(&signature.arg_types)));
+        }
+        if (signature.unmangled || overloads.iter().any(|overload| overload.unmangled)) && !overloads.is_empty(){
+            return Err(format!("'{}' keeps its C name, as it is declared with 'extern' or 'export', so it cannot be overloaded", name));
+        }
+        signature.c_name = format!("{}{}", module.prefix, name);
+        if signature.unmangled {signature.c_name = name.to_string();}
+        // the C 'main' is generated around the one of the program
+        if module.is_main && name == "main"{
+            if !overloads.is_empty() {return Err("'main' cannot be overloaded".to_string());}
+            signature.c_name = "__chal_main".to_string();
+        }
+        overloads.push(signature.clone());
+        return Ok(signature);
+    }
+
+    // C has a single function for every name, so the overloaded ones get the argument types in
+    // their C names - "area__f64_f64" - which can be the C name of another function, like one
+    // written as "area__f64" or an overload taking a struct called "list_i32" instead of a list[i32]
+    pub fn mangle_overloads(&mut self) -> Result<(), String>{
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        for (name, overloads) in module.functions.iter_mut(){
+            if overloads.len() < 2 {continue;}
+            for overload in overloads.iter_mut(){
+                overload.c_name = format!("{}{}", module.prefix, mangle(name, &overload.arg_types));
+            }
+        }
+
+        let mut c_names: Vec<(String, String)> = Vec::new();
+        for (name, overloads) in module.functions.iter(){
+            for overload in overloads{
+                c_names.push((overload.c_name.to_string(), format!("{}({})", name, type_list(&overload.arg_types))));
+            }
+        }
+        c_names.sort();
+        for pair in c_names.windows(2){
+            if pair[0].0 == pair[1].0{
+                return Err(format!("'{}' and '{}' would both be called '{}' in C, one of them has to be renamed", pair[0].1, pair[1].1, pair[0].0));
+            }
+        }
+        return Ok(());
+    }
+
+    // the function of the module which has the given C name
+    fn function_called(&self, module: &str, c_name: &str) -> Option<String>{
+        for (name, overloads) in &self.modules[module].functions{
+            if let Some(overload) = overloads.iter().find(|overload| overload.c_name == c_name){
+                return Some(format!("{}({})", name, type_list(&overload.arg_types)));
+            }
+        }
+        return None;
+    }
+
+    // the names of other modules are qualified with the module name - "module.function"
+    fn qualify<'a>(&'a self, name: &'a str) -> Result<(&'a str, &'a str), String>{
+        match name.split_once('.'){
+            Some((module, local)) => {
+                let current = &self.modules[&self.current_module];
+                if *module != self.current_module && !current.imports.iter().any(|imported| imported == module){
+                    return Err(format!("module '{}' is not imported", module));
+                }
+                return Ok((module, local));
+            },
+            None => return Ok((self.current_module.as_str(), name)),
+        }
+    }
+
+    // returns the final C name of the function
+    pub fn define_function(&mut self, name: &String, arg_types: &Vec<VarType>, defaults: Vec<Option<Node>>) -> String{
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        let signature = module.functions
+            .get_mut(name)
+            .unwrap()
+            .iter_mut()
+            .find(|overload| overload.arg_types == *arg_types)
+            .unwrap();
+        signature.defaults = defaults;
+        return signature.c_name.to_string();
+    }
+
+    pub fn in_main_module(&self) -> bool{
+        return self.modules[&self.current_module].is_main;
+    }
+
+    pub fn get_functions(&self, name: &String) -> Result<Vec<FunctionSignature>, String>{
+        let (module, function) = self.qualify(name)?;
+        match self.modules[module].functions.get(function){
+            Some(overloads) => return Ok(overloads.clone()),
+            None            => return Err(format!("unknown function '{}'", name)),
+        }
+    }
+
+    pub fn insert_generic(&mut self, name: &String, generic: GenericFunction) -> Result<(), String>{
+        if builtins::is_builtin(name){
+            return Err(format!("'{}' is a built-in function and cannot be redefined", name));
+        }
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        if module.globals.contains_key(name){
+            return Err(format!("'{}' is already defined as a global variable", name));
+        }
+        if module.functions.contains_key(name) || module.generics.contains_key(name){
+            return Err(format!("function '{}' is already defined", name));
+        }
+        module.generics.insert(name.to_string(), generic);
+        return Ok(());
+    }
+
+    pub fn get_generic(&self, name: &String) -> Option<GenericFunction>{
+        let (module, function) = self.qualify(name).ok()?;
+        return self.modules.get(module)?.generics.get(function).cloned();
+    }
+
+    // returns the C name of the instance of the generic function for the given types,
+    // the new instances are analyzed once the whole program is - "max__i32"
+    pub fn instantiate(&mut self, name: &String, types: Vec<VarType>) -> Result<String, String>{
+        let (module, function) = self.qualify(name)?;
+        let (module, function) = (module.to_string(), function.to_string());
+        let generic = self.modules[&module].generics[&function].clone();
+        let mangled: Vec<String> = types.iter().map(|var_type| var_type.mangle()).collect();
+        let c_name: String = format!("{}{}__{}", self.modules[&module].prefix, function, mangled.join("_"));
+        if !self.instances.contains(&c_name){
+            let name: String = format!("{}[{}]", function, type_list(&types));
+            if let Some(other) = self.function_called(&module, &c_name){
+                return Err(format!("'{}' and '{}' would both be called '{}' in C, one of them has to be renamed", name, other, c_name));
+            }
+            let mut chain: Vec<String> = self.instance_chain.clone();
+            chain.push(name.to_string());
+            if chain.len() > MAX_INSTANCE_DEPTH{
+                return Err(format!(
+                    "the generic functions call for new instances without end: {} -> ...", chain[..4].join(" -> ")
+                ));
+            }
+            self.instances.push(c_name.to_string());
+            self.pending.push(Instance {
+                name: name,
+                module: module,
+                c_name: c_name.to_string(),
+                type_params: generic.type_params.iter().map(|(name, _constraint)| name.to_string()).zip(types).collect(),
+                definition: generic.definition,
+                chain: chain,
+            });
+        }
+        return Ok(c_name);
+    }
+
+    pub fn next_instance(&mut self) -> Option<Instance>{
+        if self.pending.is_empty() {return None;}
+        return Some(self.pending.remove(0));
+    }
+
+    // the names of the type parameters stand for the given types until they are set again
+    pub fn set_type_params(&mut self, type_params: Vec<(String, VarType)>){
+        self.type_params = type_params;
+    }
+
+    pub fn set_instance_chain(&mut self, chain: Vec<String>){
+        self.instance_chain = chain;
+    }
+
+    // goes back to a module which was already analyzed, for the instances of its generic functions
+    pub fn switch_module(&mut self, name: &String){
+        self.current_module = name.to_string();
+        self.scopes = vec![HashMap::new()];
+        self.defers = vec![Vec::new()];
+    }
+
+    pub fn satisfies(&self, var_type: &VarType, constraint: &str) -> bool{
+        match constraint{
+            "numeric"    => return is_numeric(var_type),
+            "comparable" => return is_numeric(var_type) || is_pointer(var_type) || self.is_plain_enum(var_type),
+            _ => return false,
+        }
+    }
+
+    // returns the C name of the global
+    pub fn insert_global(&mut self, name: &String, var_type: &VarType, value: Option<ConstValue>) -> Result<String, String>{
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        if module.globals.contains_key(name){
+            return Err(format!("global variable '{}' is already defined", name));
+        }
+        if module.functions.contains_key(name) || module.generics.contains_key(name){
+            return Err(format!("'{}' is already defined as a function", name));
+        }
+        let c_name: String = format!("{}{}", module.prefix, name);
+        if let Some(function) = self.function_called(&self.current_module, &c_name){
+            return Err(format!("the global variable '{}' and '{}' would both be called '{}' in C, one of them has to be renamed", name, function, c_name));
+        }
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        module.globals.insert(name.to_string(), Global {var_type: var_type.clone(), value: value, c_name: c_name.to_string()});
+        return Ok(c_name);
+    }
+
+    pub fn get_global(&self, name: &String) -> Option<Global>{
+        let (module, global) = self.qualify(name).ok()?;
+        return self.modules.get(module)?.globals.get(global).cloned();
+    }
+
+    // the full name of a struct or an enum of the given module
+    fn type_name(&self, module: &str, name: &str) -> String{
+        if self.modules[module].is_main {return name.to_string();}
+        return format!("{}.{}", module, name);
+    }
+
+    // only makes the name known, the fields are added once all the type names are - the types
+    // of the main module keep their names in C, so they can't have the names of the C library
+    fn declare_type(&mut self, name: &String) -> Result<String, String>{
+        let module = self.modules.get_mut(&self.current_module).unwrap();
+        if module.types.contains(name){
+            return Err(format!("type '{}' is already defined", name));
+        }
+        if module.is_main && (runtime::prelude_declares(name) || runtime::prelude_declares_type(name)){
+            return Err(format!("'{}' is already a name of the C library, the type has to be called differently", name));
+        }
+        module.types.push(name.to_string());
+        return Ok(self.type_name(&self.current_module, name));
+    }
+
+    pub fn declare_struct(&mut self, name: &String) -> Result<String, String>{
+        let full_name = self.declare_type(name)?;
+        self.structs.insert(full_name.to_string(), Vec::new());
+        return Ok(full_name);
+    }
+
+    pub fn declare_enum(&mut self, name: &String) -> Result<String, String>{
+        let full_name = self.declare_type(name)?;
+        self.enums.insert(full_name.to_string(), Vec::new());
+        return Ok(full_name);
+    }
+
+    pub fn define_struct(&mut self, full_name: &String, fields: StructFields){
+        self.structs.insert(full_name.to_string(), fields);
+    }
+
+    pub fn define_enum(&mut self, full_name: &String, variants: EnumVariants){
+        self.enums.insert(full_name.to_string(), variants);
+    }
+
+    pub fn get_struct(&self, full_name: &String) -> Option<&StructFields>{
+        return self.structs.get(full_name);
+    }
+
+    pub fn get_enum(&self, full_name: &String) -> Option<&EnumVariants>{
+        return self.enums.get(full_name);
+    }
+
+    // "Color.Red" or "module.Color.Red" - returns the enum type and the index of the variant
+    pub fn get_variant(&self, name: &String) -> Option<(VarType, usize)>{
+        let (type_name, variant) = name.rsplit_once('.')?;
+        let enum_type = self.resolve_type(&VarType::Struct(type_name.to_string())).ok()?;
+        let variants = match &enum_type{
+            VarType::Enum(full_name) => &self.enums[full_name],
+            _ => return None,
+        };
+        let index = variants.iter().position(|(name, _fields)| name == variant)?;
+        return Some((enum_type, index));
+    }
+
+    // the enums whose variants carry no values are plain C enums
+    pub fn is_plain_enum(&self, var_type: &VarType) -> bool{
+        match var_type{
+            VarType::Enum(name) => return self.enums[name].iter().all(|(_variant, fields)| fields.is_empty()),
+            _ => return false,
+        }
+    }
+
+    // replaces the type names written in the source with their full names - the parser
+    // can't tell a struct from an enum, so every name it finds is a struct until it is resolved
+    pub fn resolve_type(&self, var_type: &VarType) -> Result<VarType, String>{
+        match var_type{
+            VarType::Struct(name) => {
+                if let Some((_name, var_type)) = self.type_params.iter().find(|(type_param, _var_type)| type_param == name){
+                    return Ok(var_type.clone());
+                }
+                let (module, local) = self.qualify(name)?;
+                if !self.modules[module].types.iter().any(|declared| declared == local){
+                    return Err(format!("unknown type '{}'", name));
+                }
+                let full_name = self.type_name(module, local);
+                if self.enums.contains_key(&full_name) {return Ok(VarType::Enum(full_name));}
+                return Ok(VarType::Struct(full_name));
+            },
+            VarType::Array(element, length) => return Ok(VarType::Array(Box::new(self.resolve_type(element)?), *length)),
+            VarType::List(element) => return Ok(VarType::List(Box::new(self.resolve_type(element)?))),
+            VarType::Tuple(elements) => {
+                let mut resolved: Vec<VarType> = Vec::new();
+                for element in elements{
+                    resolved.push(self.resolve_type(element)?);
+                }
+                return Ok(VarType::Tuple(resolved));
+            },
+            VarType::Option(value) if **value == VarType::None => return Err("there are no options of none".to_string()),
+            VarType::Option(value) => return Ok(VarType::Option(Box::new(self.resolve_type(value)?))),
+            VarType::Result(_value, error) if **error == VarType::None => return Err("the error of a result cannot be of type none".to_string()),
+            VarType::Result(value, error) => return Ok(VarType::Result(Box::new(self.resolve_type(value)?), Box::new(self.resolve_type(error)?))),
+            VarType::Pointer(target) if **target == VarType::None => return Err("there are no pointers to none".to_string()),
+            VarType::Pointer(target) => return Ok(VarType::Pointer(Box::new(self.resolve_type(target)?))),
+            VarType::Function(arg_types, return_type) => {
+                let mut resolved: Vec<VarType> = Vec::new();
+                for arg_type in arg_types{
+                    resolved.push(self.resolve_type(arg_type)?);
+                }
+                return Ok(VarType::Function(resolved, Box::new(self.resolve_type(return_type)?)));
+            },
+            _ => return Ok(var_type.clone()),
+        }
+    }
+
+    pub fn get_field(&self, var_type: &VarType, field: &str) -> Result<VarType, String>{
+        let fields = match var_type{
+            VarType::Struct(name) => &self.structs[name],
+            _ => return Err(format!("a value of type {} has no field '{}'", var_type, field)),
+        };
+        match fields.iter().find(|(name, _field_type)| name == field){
+            Some((_name, field_type)) => return Ok(field_type.clone()),
+            None => return Err(format!("struct '{}' has no field '{}'", var_type, field)),
+        }
+    }
+
+    // the fields of a struct, or the values of all the variants of an enum
+    fn contents(&self, var_type: &VarType) -> Vec<&VarType>{
+        match var_type{
+            VarType::Struct(name) => return self.structs[name].iter().map(|(_field, field_type)| field_type).collect(),
+            VarType::Enum(name) => return self.enums[name]
+                .iter()
+                .flat_map(|(_variant, fields)| fields.iter().map(|(_field, field_type)| field_type))
+                .collect(),
+            _ => return Vec::new(),
+        }
+    }
+
+    // declares the C types of the structs and enums of the current module, every one after
+    // the types it contains (which also finds the types containing themselves) - the pointers
+    // only need the names, so those are declared up front ("typedef struct Node Node;")
+    fn declare_types(&self) -> Result<(), String>{
+        let mut declared: Vec<VarType> = Vec::new();
+        let mut types: Vec<VarType> = Vec::new();
+        for name in &self.modules[&self.current_module].types{
+            let var_type = self.resolve_type(&VarType::Struct(name.to_string()))?;
+            let c_name: String = var_type.to_c().trim().to_string();
+            if self.is_plain_enum(&var_type){
+                self.declare_c_type(&var_type, &mut Vec::new(), &mut declared)?;
+            }else{
+                runtime::declare_type(&format!("struct {}", c_name), format!("typedef struct {} {};\n", c_name, c_name));
+            }
+            types.push(var_type);
+        }
+        for var_type in types{
+            self.declare_c_type(&var_type, &mut Vec::new(), &mut declared)?;
+        }
+        return Ok(());
+    }
+
+    fn declare_c_type(&self, var_type: &VarType, path: &mut Vec<String>, declared: &mut Vec<VarType>) -> Result<(), String>{
+        if declared.contains(var_type) {return Ok(());}
+        let name: String = var_type.to_string();
+        if path.contains(&name){
+            return Err(format!("type '{}' contains itself: {} -> {}", path[0], path.join(" -> "), name));
+        }
+        path.push(name);
+        for contained in self.contents(var_type){
+            for contained in contained_types(contained){
+                self.declare_c_type(&contained, path, declared)?;
+            }
+        }
+        path.pop();
+
+        let c_name: String = var_type.to_c().trim().to_string();
+        let mut definition: String = String::new();
+        match var_type{
+            VarType::Struct(name) => {
+                definition.push_str(&format!("struct {} {{\n", c_name));
+                for (field, field_type) in &self.structs[name]{
+                    definition.push_str(&format!("    {}{};\n", field_type.to_c(), field));
+                }
+                definition.push_str("};\n");
+            },
+            // "typedef enum { Color__Red, Color__Green } Color;" - the enums carrying values are
+            // tagged unions, with a struct of the values for every variant which has any
+            VarType::Enum(name) => {
+                let variants = &self.enums[name];
+                let tags: Vec<String> = variants.iter().map(|(variant, _fields)| format!("{}__{}", c_name, variant)).collect();
+                if self.is_plain_enum(var_type){
+                    definition.push_str(&format!("typedef enum {{ {} }} {};\n", tags.join(", "), c_name));
+                }else{
+                    definition.push_str(&format!("enum {{ {} }};\n", tags.join(", ")));
+                    definition.push_str(&format!("struct {} {{\n    int tag;\n    union {{\n", c_name));
+                    for (variant, fields) in variants{
+                        if fields.is_empty() {continue;}
+                        definition.push_str("        struct {");
+                        for (field, field_type) in fields{
+                            definition.push_str(&format!(" {}{};", field_type.to_c(), field));
+                        }
+                        definition.push_str(&format!(" }} {};\n", variant));
+                    }
+                    definition.push_str("    } as;\n};\n");
+                }
+            },
+            _ => (),
+        }
+        runtime::declare_type(&c_name, definition);
+        declared.push(var_type.clone());
+        return Ok(());
+    }
+
+    pub fn module_includes(&self) -> bool{
+        return self.modules[&self.current_module].includes;
+    }
+
+    pub fn link(&mut self, library: &str){
+        if !self.libraries.iter().any(|linked| linked == library){
+            self.libraries.push(library.to_string());
+        }
+    }
+
+    pub fn include(&mut self, header: &str){
+        self.modules.get_mut(&self.current_module).unwrap().includes = true;
+        if !self.headers.iter().any(|included| included == header){
+            self.headers.push(header.to_string());
+        }
+    }
+
+    // the types a function called from C can take and return are the ones C has itself - the
+    // numbers, the strings, the pointers, the plain enums and the structs of those - their
+    // definitions are copied to the header
+    pub fn export_type(&mut self, var_type: &VarType) -> Result<(), String>{
+        match var_type{
+            VarType::Pointer(target) => return self.export_type(target),
+            VarType::Struct(name) => {
+                let c_name: String = var_type.to_c().trim().to_string();
+                if self.exported_types.contains(&c_name) {return Ok(());}
+                self.exported_types.push(format!("struct {}", c_name));
+                self.exported_types.push(c_name);
+                for (_field, field_type) in self.structs[name].clone(){
+                    self.export_type(&field_type)?;
+                }
+                return Ok(());
+            },
+            VarType::Enum(_name) if self.is_plain_enum(var_type) => {
+                let c_name: String = var_type.to_c().trim().to_string();
+                if !self.exported_types.contains(&c_name) {self.exported_types.push(c_name);}
+                return Ok(());
+            },
+            VarType::Enum(_) | VarType::Array(_, _) | VarType::List(_) | VarType::Tuple(_) | VarType::Option(_) |
+            VarType::Result(_, _) | VarType::Function(_, _) | VarType::Generic(_) | VarType::Auto => {
+                return Err(format!("C has no type like {}", var_type));
+            },
+            _ => return Ok(()),
+        }
+    }
+}
+
+// the structs and enums a value of the given type holds in place (the list elements are on
+// the heap, but the C helpers of a list still need the whole type of its elements)
+fn contained_types(var_type: &VarType) -> Vec<VarType>{
+    match var_type{
+        VarType::Struct(_name) => return vec![var_type.clone()],
+        VarType::Enum(_name) => return vec![var_type.clone()],
+        VarType::Array(element, _length) => return contained_types(element),
+        VarType::List(element) => return contained_types(element),
+        VarType::Tuple(elements) => return elements.iter().flat_map(contained_types).collect(),
+        VarType::Option(value) => return contained_types(value),
+        VarType::Result(value, error) => return [contained_types(value), contained_types(error)].concat(),
+        _ => return Vec::new(),
+    }
+}
+
+// whether a part of the type is still unknown - "none" is an option of any type
+pub fn is_partial(var_type: &VarType) -> bool{
+    match var_type{
+        VarType::Auto => return true,
+        VarType::Pointer(target) => return is_partial(target),
+        VarType::Option(value) => return is_partial(value),
+        VarType::Result(value, error) => return is_partial(value) || is_partial(error),
+        VarType::Tuple(elements) => return elements.iter().any(is_partial),
+        _ => return false,
+    }
+}
+
+// the variants a match sees in an option or a result, with the fields of their tagged struct
+pub fn sum_variants(var_type: &VarType) -> EnumVariants{
+    match var_type{
+        VarType::Option(value) => {
+            return vec![("some".to_string(), vec![("value".to_string(), *value.clone())]), ("none".to_string(), Vec::new())];
+        },
+        VarType::Result(value, error) => {
+            let mut fields: StructFields = Vec::new();
+            if **value != VarType::None {fields.push(("value".to_string(), *value.clone()));}
+            return vec![("ok".to_string(), fields), ("err".to_string(), vec![("error".to_string(), *error.clone())])];
+        },
+        _ => return Vec::new(),
+    }
+}
+
+pub fn is_numeric(var_type: &VarType) -> bool{
+    match var_type{
+        VarType::I8  | VarType::I16 | VarType::I32 | VarType::I64 |
+        VarType::U8  | VarType::U16 | VarType::U32 | VarType::U64 |
+        VarType::F32 | VarType::F64 => return true,
+        _ => return false,
+    }
+}
+
+pub fn is_float(var_type: &VarType) -> bool{
+    return *var_type == VarType::F32 || *var_type == VarType::F64;
+}
+
+pub fn is_signed(var_type: &VarType) -> bool{
+    return matches!(var_type, VarType::I8 | VarType::I16 | VarType::I32 | VarType::I64);
+}
+
+pub fn is_unsigned(var_type: &VarType) -> bool{
+    return matches!(var_type, VarType::U8 | VarType::U16 | VarType::U32 | VarType::U64);
+}
+
+// the pointers and the functions, which are C pointers
+pub fn is_pointer(var_type: &VarType) -> bool{
+    return matches!(var_type, VarType::Pointer(_) | VarType::Function(_, _));
+}
+
+// the numbers and the bools are truth values, zero being false
+pub fn is_condition(var_type: &VarType) -> bool{
+    return is_numeric(var_type) || *var_type == VarType::Bool;
+}
+
+// numeric values are implicitly converted between each other (the same way C does it), the bools
+// become numbers (but the numbers don't become bools), and null can be assigned to any pointer -
+// alloc(), "none", "ok(x)" and "err(e)" leave a part of their type unknown (auto), which any type fills
+pub fn is_compatible(from: &VarType, to: &VarType) -> bool{
+    if from == to || (is_numeric(from) && is_numeric(to)) {return true;}
+    if *from == VarType::Bool && is_numeric(to) {return true;}
+    match (from, to){
+        (VarType::Pointer(target), VarType::Pointer(_)) if **target == VarType::Auto => return true,
+        (VarType::Option(value), VarType::Option(_)) => return **value == VarType::Auto,
+        (VarType::Result(value, error), VarType::Result(to_value, to_error)) => {
+            return (value == to_value || **value == VarType::Auto) && (error == to_error || **error == VarType::Auto);
+        },
+        _ => (),
+    }
+    return *from == VarType::Pointer(Box::new(VarType::None)) && is_pointer(to);
+}
+
+// orders the numeric types by how many values they can hold
+pub fn numeric_rank(var_type: &VarType) -> u32{
+    match var_type{
+        VarType::I8  => return 1,
+        VarType::U8  => return 2,
+        VarType::I16 => return 3,
+        VarType::U16 => return 4,
+        VarType::I32 => return 5,
+        VarType::U32 => return 6,
+        VarType::F32 => return 7,
+        VarType::I64 => return 8,
+        VarType::U64 => return 9,
+        VarType::F64 => return 10,
+        _ => return 0,
+    }
+}
+
+// how undesirable it is to implicitly convert a value - widening is preferred over narrowing
+// and integers prefer to become f64 (like the C literals do)
+pub fn conversion_cost(from: &VarType, to: &VarType) -> Option<u32>{
+    if from == to {return Some(0);}
+    if !is_compatible(from, to) {return None;}
+    if !is_float(from) && is_float(to){
+        return Some(if *to == VarType::F64 {20} else {21});
+    }
+    if is_float(from) && !is_float(to) {return Some(100);}
+    // the negative values are lost
+    if is_signed(from) && is_unsigned(to) {return Some(100 + numeric_rank(to).abs_diff(numeric_rank(from)));}
+    if numeric_rank(to) > numeric_rank(from) {return Some(numeric_rank(to) - numeric_rank(from));}
+    return Some(100 + numeric_rank(from) - numeric_rank(to));
+}
+
+// the numeric type holding the values of both - a signed and an unsigned integer need a signed
+// type wider than the unsigned one, "-1" and "2" make an i16
+pub fn wider_numeric(type1: &VarType, type2: &VarType) -> VarType{
+    let (signed, unsigned) = match (type1, type2){
+        _ if is_signed(type1) && is_unsigned(type2) => (type1, type2),
+        _ if is_unsigned(type1) && is_signed(type2) => (type2, type1),
+        _ if numeric_rank(type1) > numeric_rank(type2) => return type1.clone(),
+        _ => return type2.clone(),
+    };
+    let widened: VarType = match unsigned{
+        VarType::U8  => VarType::I16,
+        VarType::U16 => VarType::I32,
+        _ => VarType::I64,
+    };
+    if numeric_rank(signed) > numeric_rank(&widened) {return signed.clone();}
+    return widened;
+}
+
+// the type both values can be converted to, used for the elements of the array literals
+pub fn common_type(type1: &VarType, type2: &VarType) -> Option<VarType>{
+    if type1 == type2 {return Some(type1.clone());}
+    match (type1, type2){
+        (VarType::Array(element1, length1), VarType::Array(element2, length2)) if length1 == length2 => {
+            return common_type(element1, element2).map(|element| VarType::Array(Box::new(element), *length1));
+        },
+        // "[some(1), none]" - the unknown parts of the types take the known ones
+        (VarType::Auto, _) => return Some(type2.clone()),
+        (_, VarType::Auto) => return Some(type1.clone()),
+        (VarType::Option(value1), VarType::Option(value2)) => {
+            return common_type(value1, value2).map(|value| VarType::Option(Box::new(value)));
+        },
+        (VarType::Result(value1, error1), VarType::Result(value2, error2)) => {
+            let value = common_type(value1, value2)?;
+            return common_type(error1, error2).map(|error| VarType::Result(Box::new(value), Box::new(error)));
+        },
+        _ if is_pointer(type1) && is_compatible(type2, type1) => return Some(type1.clone()),
+        _ if is_pointer(type2) && is_compatible(type1, type2) => return Some(type2.clone()),
+        _ if is_numeric(type1) && is_numeric(type2) => return Some(wider_numeric(type1, type2)),
+        _ => return None,
+    }
+}
+
+// binds the type parameters in the type of an argument to the type of the given value -
+// the numbers bind a type parameter to the widest of them, null tells nothing
+pub fn infer_type_params(arg_type: &VarType, given: &VarType, bindings: &mut Vec<(String, VarType)>) -> Result<(), String>{
+    match (arg_type, given){
+        (_, VarType::Pointer(target)) if **target == VarType::None => return Ok(()),
+        (_, VarType::Auto) => return Ok(()),
+        (VarType::Generic(name), _) => {
+            let bound = match bindings.iter_mut().find(|(type_param, _var_type)| type_param == name){
+                Some((_name, bound)) => bound,
+                None => {
+                    bindings.push((name.to_string(), given.clone()));
+                    return Ok(());
+                },
+            };
+            if is_numeric(bound) && is_numeric(given){
+                *bound = common_type(bound, given).unwrap();
+            }else if bound != given{
+                return Err(format!("the type parameter '{}' cannot be both {} and {}", name, bound, given));
+            }
+            return Ok(());
+        },
+        (VarType::Array(element, length), VarType::Array(given_element, given_length)) if length == given_length => {
+            return infer_type_params(element, given_element, bindings);
+        },
+        (VarType::List(element), VarType::List(given_element)) => return infer_type_params(element, given_element, bindings),
+        // an array literal becomes a list of its elements - the other arrays are reported as they are checked
+        (VarType::List(element), VarType::Array(given_element, _length)) => return infer_type_params(element, given_element, bindings),
+        (VarType::Option(value), VarType::Option(given_value)) => return infer_type_params(value, given_value, bindings),
+        (VarType::Result(value, error), VarType::Result(given_value, given_error)) => {
+            infer_type_params(value, given_value, bindings)?;
+            return infer_type_params(error, given_error, bindings);
+        },
+        (VarType::Tuple(elements), VarType::Tuple(given_elements)) if elements.len() == given_elements.len() => {
+            for i in 0..elements.len(){
+                infer_type_params(&elements[i], &given_elements[i], bindings)?;
+            }
+            return Ok(());
+        },
+        (VarType::Pointer(target), VarType::Pointer(given_target)) => return infer_type_params(target, given_target, bindings),
+        (VarType::Function(arg_types, return_type), VarType::Function(given_args, given_return)) if arg_types.len() == given_args.len() => {
+            for i in 0..arg_types.len(){
+                infer_type_params(&arg_types[i], &given_args[i], bindings)?;
+            }
+            return infer_type_params(return_type, given_return, bindings);
+        },
+        // the types which don't match are reported once the arguments are checked
+        _ => return Ok(()),
+    }
+}
+
+// replaces the type parameters with the types they are bound to
+pub fn substitute(var_type: &VarType, bindings: &Vec<(String, VarType)>) -> VarType{
+    match var_type{
+        VarType::Generic(name) => {
+            return bindings
+                .iter()
+                .find(|(type_param, _var_type)| type_param == name)
+                .map(|(_name, bound)| bound.clone())
+                .unwrap_or(var_type.clone());
+        },
+        VarType::Array(element, length) => return VarType::Array(Box::new(substitute(element, bindings)), *length),
+        VarType::List(element) => return VarType::List(Box::new(substitute(element, bindings))),
+        VarType::Tuple(elements) => return VarType::Tuple(elements.iter().map(|element| substitute(element, bindings)).collect()),
+        VarType::Option(value) => return VarType::Option(Box::new(substitute(value, bindings))),
+        VarType::Result(value, error) => return VarType::Result(Box::new(substitute(value, bindings)), Box::new(substitute(error, bindings))),
+        VarType::Pointer(target) => return VarType::Pointer(Box::new(substitute(target, bindings))),
+        VarType::Function(arg_types, return_type) => {
+            let arg_types: Vec<VarType> = arg_types.iter().map(|arg_type| substitute(arg_type, bindings)).collect();
+            return VarType::Function(arg_types, Box::new(substitute(return_type, bindings)));
+        },
+        _ => return var_type.clone(),
+    }
+}
+
+// whether the type parameter is a part of the type
+pub fn contains_type_param(var_type: &VarType, name: &String) -> bool{
+    match var_type{
+        VarType::Generic(type_param) => return type_param == name,
+        VarType::Array(element, _length) => return contains_type_param(element, name),
+        VarType::List(element) => return contains_type_param(element, name),
+        VarType::Tuple(elements) => return elements.iter().any(|element| contains_type_param(element, name)),
+        VarType::Option(value) => return contains_type_param(value, name),
+        VarType::Result(value, error) => return contains_type_param(value, name) || contains_type_param(error, name),
+        VarType::Pointer(target) => return contains_type_param(target, name),
+        VarType::Function(arg_types, return_type) => {
+            return arg_types.iter().any(|arg_type| contains_type_param(arg_type, name)) || contains_type_param(return_type, name);
+        },
+        _ => return false,
+    }
+}
+
+pub fn type_list(types: &Vec<VarType>) -> String{
+    return types
+        .iter()
+        .map(|var_type| var_type.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+}
+
+pub fn check_arguments(name: &str, arg_types: &Vec<VarType>, given: &Vec<VarType>) -> Result<(), String>{
+    if arg_types.len() != given.len(){
+        return Err(format!("'{}' takes {} argument(s) but {} were given", name, arg_types.len(), given.len()));
+    }
+    for i in 0..given.len(){
+        if !is_compatible(&given[i], &arg_types[i]){
+            return Err(format!("argument {} of '{}' must be of type {}, found {}", i + 1, name, arg_types[i], given[i]));
+        }
+    }
+    return Ok(());
+}
+
+// picks the overload whose arguments are the cheapest to convert to, returning its index
+// the C name of an overload, without the prefix of its module - "area__f64_f64", "tick__none"
+pub fn mangle(name: &str, arg_types: &Vec<VarType>) -> String{
+    let arg_types: Vec<String> = arg_types.iter().map(|arg_type| arg_type.mangle()).collect();
+    let arg_types: String = if arg_types.is_empty() {"none".to_string()} else {arg_types.join("_")};
+    return format!("{}__{}", name, arg_types);
+}
+
+pub fn resolve_overload(name: &str, candidates: &Vec<Vec<VarType>>, given: &Vec<VarType>) -> Result<usize, String>{
+    if candidates.len() == 1{
+        check_arguments(name, &candidates[0], given)?;
+        return Ok(0);
+    }
+
+    let mut best: Vec<usize> = Vec::new();
+    let mut best_cost = u32::MAX;
+    for (i, candidate) in candidates.iter().enumerate(){
+        if candidate.len() != given.len() {continue;}
+        let cost: Option<u32> = candidate
+            .iter()
+            .zip(given.iter())
+            .map(|(arg_type, given_type)| conversion_cost(given_type, arg_type))
+            .sum();
+        match cost{
+            Some(cost) if cost < best_cost => {best = vec![i]; best_cost = cost;},
+            Some(cost) if cost == best_cost => best.push(i),
+            _ => (),
+        }
+    }
+
+    match best.len(){
+        0 => return Err(format!("no overload of '{}' takes arguments of type ({})", name, type_list(given))),
+        1 => return Ok(best[0]),
+        _ => {
+            let overloads: Vec<String> = best
+                .iter()
+                .map(|i| format!("{}({})", name, type_list(&candidates[*i])))
+                .collect();
+            return Err(format!("the call {}({}) is ambiguous between {}", name, type_list(given), overloads.join(" and ")));
+        },
+    }
+}
+
+// checks the whole program, resolves the 'auto' types and annotates the nodes
+// with everything the code generation needs to know about the types - the modules
+// come ordered so that every module is after the ones it imports
+// a library is called from C, so it has no 'main' function
+pub fn analyze(modules: &mut Vec<Module>, library: bool) -> Result<SymbolTable, String>{
+    let mut table = SymbolTable::new();
+    let main_module = modules.len() - 1;
+    for (i, module) in modules.iter_mut().enumerate(){
+        table.enter_module(&module.name, i == main_module);
+        let error = |error: String| format!("{}.ch: {}", module.name, error);
+
+        // the imported modules, the headers, the types, the functions and the globals can be used anywhere in their module
+        for node in module.nodes.iter_mut(){
+            match node{
+                Node::Import(val)  => val.analyze(&mut table).map_err(error)?,
+                Node::Include(val) => val.analyze(&mut table).map_err(error)?,
+                _ => VarType::None,
+            };
+        }
+        for node in module.nodes.iter_mut(){
+            match node{
+                Node::StructDefinition(val) => val.declare(&mut table).map_err(error)?,
+                Node::EnumDefinition(val)   => val.declare(&mut table).map_err(error)?,
+                _ => (),
+            }
+        }
+        for node in module.nodes.iter_mut(){
+            match node{
+                Node::StructDefinition(val) => val.define(&mut table).map_err(error)?,
+                Node::EnumDefinition(val)   => val.define(&mut table).map_err(error)?,
+                _ => (),
+            }
+        }
+        table.declare_types().map_err(error)?;
+
+        for node in module.nodes.iter_mut(){
+            if let Node::FunctionDefinition(val) = node {val.declare(&mut table).map_err(error)?;}
+        }
+        table.mangle_overloads().map_err(error)?;
+        for node in module.nodes.iter_mut(){
+            if let Node::Global(val) = node {val.declare(&mut table).map_err(error)?;}
+        }
+        for node in module.nodes.iter_mut(){
+            if let Node::FunctionDefinition(val) = node {val.define(&mut table).map_err(error)?;}
+        }
+
+        for node in module.nodes.iter_mut(){
+            match node{
+                Node::FunctionDefinition(_) | Node::StructDefinition(_) | Node::EnumDefinition(_) |
+                Node::Import(_) | Node::Include(_) | Node::Global(_) => (),
+                _ => return Err(error("only definitions are allowed at the top level of a module".to_string())),
+            }
+            node.analyze(&mut table).map_err(error)?;
+        }
+    }
+
+    // the generic functions are analyzed in their own modules for every combination of types
+    // they are called with - the instances can call other generic functions too
+    let mut instances: Vec<Node> = Vec::new();
+    while let Some(mut instance) = table.next_instance(){
+        table.switch_module(&instance.module);
+        table.set_type_params(instance.type_params);
+        // the names of the deepest instances are too long to read, the chain starts the error instead
+        let name: String = if instance.chain.len() < MAX_INSTANCE_DEPTH {instance.name} else {instance.chain[0].to_string()};
+        table.set_instance_chain(instance.chain);
+        if let Node::FunctionDefinition(val) = &mut instance.definition{
+            val.instantiate(&mut table, &instance.c_name)
+                .map_err(|error| format!("{}.ch: {} (in {})", instance.module, error, name))?;
+        }
+        instances.push(instance.definition);
+    }
+    table.set_type_params(Vec::new());
+    table.set_instance_chain(Vec::new());
+    table.switch_module(&modules[main_module].name);
+    modules[main_module].nodes.extend(instances);
+
+    if library{
+        if table.get_functions(&"main".to_string()).is_ok(){
+            return Err(format!("{}.ch: a library cannot have a 'main' function", modules[main_module].name));
+        }
+    }else if table.get_functions(&"main".to_string()).is_err(){
+        return Err(format!("{}.ch: the program has no 'main' function", modules[main_module].name));
+    }
+    return Ok(table);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn signature(arg_types: Vec<VarType>) -> FunctionSignature{
+        return FunctionSignature {
+            arg_names: (0..arg_types.len()).map(|i| format!("a{}", i)).collect(),
+            arg_types: arg_types,
+            defaults: Vec::new(),
+            return_type: VarType::None,
+            c_name: String::new(),
+            unmangled: false,
+        };
+    }
+
+    fn mangled_module(functions: Vec<(&str, Vec<VarType>)>) -> Result<SymbolTable, String>{
+        let mut table = SymbolTable::new();
+        table.enter_module(&"prog".to_string(), true);
+        for (name, arg_types) in functions{
+            table.insert_function(&name.to_string(), signature(arg_types))?;
+        }
+        table.mangle_overloads()?;
+        return Ok(table);
+    }
+
+    #[test]
+    fn mangle_names_the_argument_types(){
+        assert_eq!(mangle("area", &vec![VarType::F64, VarType::F64]), "area__f64_f64");
+        assert_eq!(mangle("tick", &vec![]), "tick__none");
+        assert_eq!(mangle("sum", &vec![VarType::List(Box::new(VarType::I32))]), "sum__list_i32");
+        assert_eq!(mangle("at", &vec![VarType::Pointer(Box::new(VarType::Struct("geo.Point".to_string())))]), "at__ptr_geo__Point");
+    }
+
+    #[test]
+    fn only_overloads_are_mangled(){
+        let table = mangled_module(vec![("area", vec![VarType::F64]), ("area", vec![VarType::I32]), ("tick", vec![])]).unwrap();
+        let c_names = |name: &str| -> Vec<String> {
+            table.get_functions(&name.to_string()).unwrap().iter().map(|overload| overload.c_name.to_string()).collect()
+        };
+        assert_eq!(c_names("area"), vec!["__main__area__f64", "__main__area__i32"]);
+        assert_eq!(c_names("tick"), vec!["__main__tick"]);
+    }
+
+    #[test]
+    fn mangled_names_which_clash_are_errors(){
+        let error = mangled_module(vec![("area", vec![VarType::F64]), ("area", vec![VarType::I32]), ("area__f64", vec![VarType::F64])]);
+        assert!(error.unwrap_err().contains("'__main__area__f64' in C"));
+
+        // a struct called "list_i32" and a list[i32] have the same name in the mangled ones
+        let error = mangled_module(vec![
+            ("f", vec![VarType::List(Box::new(VarType::I32))]),
+            ("f", vec![VarType::Struct("list_i32".to_string())]),
+        ]);
+        assert!(error.unwrap_err().contains("'__main__f__list_i32' in C"));
+    }
+
+    #[test]
+    fn resolve_overload_prefers_the_cheapest_conversion(){
+        let candidates = vec![vec![VarType::F64], vec![VarType::I32], vec![VarType::I64]];
+        assert_eq!(resolve_overload("f", &candidates, &vec![VarType::F64]), Ok(0));
+        assert_eq!(resolve_overload("f", &candidates, &vec![VarType::I32]), Ok(1));
+        assert_eq!(resolve_overload("f", &candidates, &vec![VarType::I16]), Ok(1));
+        assert_eq!(resolve_overload("f", &candidates, &vec![VarType::U8]), Ok(1));
+
+        // the negative values would be lost in the unsigned overload
+        let candidates = vec![vec![VarType::U8, VarType::U8], vec![VarType::I64, VarType::I64]];
+        assert_eq!(resolve_overload("min", &candidates, &vec![VarType::I8, VarType::U8]), Ok(1));
+    }
+
+    #[test]
+    fn resolve_overload_reports_missing_and_ambiguous_overloads(){
+        let candidates = vec![vec![VarType::I32, VarType::F64], vec![VarType::F64, VarType::I32]];
+        let error = resolve_overload("f", &candidates, &vec![VarType::I32, VarType::I32]).unwrap_err();
+        assert!(error.contains("ambiguous"), "{}", error);
+
+        let error = resolve_overload("f", &candidates, &vec![VarType::Str, VarType::I32]).unwrap_err();
+        assert!(error.starts_with("no overload of 'f'"), "{}", error);
+
+        let error = resolve_overload("f", &candidates, &vec![VarType::I32]).unwrap_err();
+        assert!(error.starts_with("no overload of 'f'"), "{}", error);
+    }
+}
@@ -18,6 +18,10 @@ impl<T> Stack<T>{
         self.values.pop_back().unwrap()
     }
     
+    pub fn len(&self) -> usize{
+        self.values.len()
+    }
+
     pub fn empty(&self) -> bool{
         self.values.is_empty()
     }
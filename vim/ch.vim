@@ -4,8 +4,9 @@ end
 
 au BufRead, BufNewFile *.ch set filetype chal
 
-syn keyword varTypes auto none i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 str
-syn keyword keywords fn return end if elif else while for
+syn keyword varTypes auto none i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 str bool list option result
+syn keyword keywords fn const struct enum match case return end if elif else while for in import use break continue defer extern export
+syn keyword constants null true false some ok err
 syn region return start='->' end=':' contains=varTypes
 
 syn match comment "#.*$"
@@ -21,6 +22,7 @@ let b:current_syntax = "chal"
 hi def link varTypes Type
 hi def link keywords Statement
 hi def link number   Constant
+hi def link constants Constant
 hi def link string   Constant
 hi def link comment  Comment
 hi def link return   MoreMsg